@@ -0,0 +1,35 @@
+//! Compares the strategies for day09 part 2 on generated polygons, where the
+//! compressed grid grows with the square of the number of corners.
+
+use std::hint::black_box;
+
+use aoc_2025::day09::{Strategy, largest_inside_rectangle};
+use aoc_2025::generate::generate;
+use aoc_utils::cli::Generate;
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+
+fn strategies(c: &mut Criterion) {
+    let mut group = c.benchmark_group("largest_inside_rectangle");
+    group.sample_size(10);
+    for size in [500, 2000, 4000] {
+        let input = generate(&Generate {
+            year: 2025,
+            day: 9,
+            size: Some(size),
+            seed: 0,
+        })
+        .unwrap();
+        let tiles = aoc_2025::day09::process(&input, &Default::default())
+            .unwrap()
+            .tiles;
+        for (name, strategy) in [("grid", Strategy::Grid), ("sweep", Strategy::Sweep)] {
+            group.bench_with_input(BenchmarkId::new(name, size), &tiles, |b, tiles| {
+                b.iter(|| largest_inside_rectangle(black_box(tiles), strategy))
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, strategies);
+criterion_main!(benches);
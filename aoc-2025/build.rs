@@ -0,0 +1,161 @@
+//! Generates one test per day and part that checks the answer for the real
+//! input.
+//!
+//! The days come from the `aoc!` invocation in `src/main.rs`. Inputs are read
+//! from `../input/<year>/dayXX.txt`, where the runner downloads them, and the
+//! recorded answers from `../input/<year>/answers.txt`, one per line:
+//!
+//! ```text
+//! day01 part1 1092
+//! day01 part2 6616
+//! ```
+//!
+//! Tests whose input or answer is missing are generated with `#[ignore]`, so
+//! they are reported as ignored rather than passing without checking.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+struct Day {
+    year: String,
+    name: String,
+    params: Option<String>,
+    process: Option<String>,
+    parts: Vec<String>,
+}
+
+/// Parses lines like `2025 day08 with Params => process => part1, part2;`,
+/// ignoring a trailing `=> visualize`.
+fn parse_day(line: &str) -> Option<Day> {
+    let line = line.trim().strip_suffix(';')?;
+    let mut segments = line.split("=>").map(str::trim);
+
+    let mut head = segments.next()?.split_whitespace();
+    let year = head.next()?;
+    let name = head.next()?;
+    if !year.chars().all(|c| c.is_ascii_digit()) || !name.starts_with("day") {
+        return None;
+    }
+    let params = match (head.next(), head.next()) {
+        (Some("with"), Some(params)) => Some(params.to_owned()),
+        _ => None,
+    };
+
+    let segments: Vec<_> = segments.collect();
+    let (process, parts) = match segments.as_slice() {
+        [parts] => (None, parts),
+        [process, parts] | [process, parts, _] => (Some(process.to_string()), parts),
+        _ => return None,
+    };
+
+    Some(Day {
+        year: year.to_owned(),
+        name: name.to_owned(),
+        params,
+        process,
+        parts: parts.split(',').map(|p| p.trim().to_owned()).collect(),
+    })
+}
+
+fn read_answers(path: &Path) -> HashMap<(String, String), String> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return HashMap::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let day = fields.next()?.to_owned();
+            let part = fields.next()?.to_owned();
+            let answer = fields.next()?.to_owned();
+            Some(((day, part), answer))
+        })
+        .collect()
+}
+
+fn test_for(day: &Day, part: &str, input: &Path, answer: Option<&String>) -> String {
+    let mut test = String::new();
+    match (input.exists(), answer) {
+        (false, _) => writeln!(test, "#[ignore = \"no input at {}\"]", input.display()),
+        (true, None) => writeln!(
+            test,
+            "#[ignore = \"no recorded answer for {} {}\"]",
+            day.name, part
+        ),
+        (true, Some(_)) => Ok(()),
+    }
+    .unwrap();
+
+    let process = match (&day.process, &day.params) {
+        (None, _) => "input.as_str()".to_owned(),
+        (Some(process), None) => format!("{}::{}(&input).unwrap()", day.name, process),
+        (Some(process), Some(params)) => format!(
+            "{}::{}(&input, &{}::{}::default()).unwrap()",
+            day.name, process, day.name, params
+        ),
+    };
+    writeln!(
+        test,
+        "#[test]
+fn {day}_{part}() {{
+    let input = std::fs::read_to_string({input:?}).unwrap();
+    let input = {process};
+    assert_eq!(aoc_utils::Answer::to_answer(&{day}::{part}(&input)), {answer:?});
+}}
+",
+        day = day.name,
+        input = input,
+        answer = answer.map_or("", String::as_str),
+    )
+    .unwrap();
+    test
+}
+
+fn main() {
+    let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    let main_rs = manifest_dir.join("src/main.rs");
+    println!("cargo::rerun-if-changed={}", main_rs.display());
+
+    let days: Vec<_> = std::fs::read_to_string(&main_rs)
+        .unwrap()
+        .lines()
+        .filter_map(parse_day)
+        .collect();
+
+    // Watch the directory of inputs for each year, or the root of all
+    // inputs before the year has one. Cargo scans directories for changes,
+    // which notices inputs and answers added to them. A missing path counts
+    // as changed on every build, so the root is created up front, where the
+    // runner would download inputs to anyway.
+    let input_root = manifest_dir.join("../input");
+    let _ = std::fs::create_dir_all(&input_root);
+    let mut watched = vec![];
+    for day in &days {
+        let year_dir = input_root.join(&day.year);
+        let dir = if year_dir.exists() {
+            year_dir
+        } else {
+            input_root.clone()
+        };
+        if !watched.contains(&dir) {
+            println!("cargo::rerun-if-changed={}", dir.display());
+            watched.push(dir);
+        }
+    }
+
+    let mut tests = String::from("use aoc_2025::*;\n\n");
+    for day in &days {
+        let input_dir = input_root.join(&day.year);
+        let input = input_dir.join(format!("{}.txt", day.name));
+        let answers_path = input_dir.join("answers.txt");
+        let answers = read_answers(&answers_path);
+        for part in &day.parts {
+            let answer = answers.get(&(day.name.clone(), part.clone()));
+            tests.push_str(&test_for(day, part, &input, answer));
+        }
+    }
+
+    let out = PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("real_inputs.rs");
+    std::fs::write(out, tests).unwrap();
+}
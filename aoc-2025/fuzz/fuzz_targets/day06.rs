@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(input) = std::str::from_utf8(data)
+        .ok()
+        .and_then(|input| aoc_2025::day06::process(input).ok())
+    {
+        let _ = aoc_2025::day06::part1(&input);
+        let _ = aoc_2025::day06::part2(&input);
+    }
+});
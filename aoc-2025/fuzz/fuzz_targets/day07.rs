@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(input) = std::str::from_utf8(data)
+        .ok()
+        .and_then(|input| aoc_2025::day07::process(input).ok())
+    {
+        aoc_2025::day07::part1(&input);
+        aoc_2025::day07::part2(&input);
+    }
+});
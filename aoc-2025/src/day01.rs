@@ -1,107 +1,150 @@
 use std::str::FromStr;
 
-use winnow::error::InputError;
-use winnow::prelude::*;
-use winnow::token::one_of;
+use aoc_utils::parse::ParseReport;
 
-struct Dial {
-    num: i32,
+type Input = Vec<Rotation>;
+
+/// A dial with positions `0..size` that counts how often each watched
+/// position is clicked onto, including where a rotation ends.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dial {
+    size: i32,
+    position: i32,
+    watched: Vec<i32>,
+}
+
+/// Where the dial ended up after one rotation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub position: i32,
+    /// How often each watched position was reached, in the order they were
+    /// given to [`Dial::new`].
+    pub crossings: Vec<i32>,
 }
 
 impl Default for Dial {
+    /// The puzzle's dial: 100 positions, starting at 50, watching 0.
     fn default() -> Self {
-        Self { num: 50 }
+        Self::new(100, 50, vec![0])
     }
 }
 
 impl Dial {
-    fn rotate(&mut self, rot: Rotation) {
-        self.num = match rot {
-            Rotation::Left(count) => self.num - count,
-            Rotation::Right(count) => self.num + count,
+    /// Panics unless `size` is positive and `start` and every watched
+    /// position are in `0..size`.
+    pub fn new(size: i32, start: i32, watched: Vec<i32>) -> Self {
+        assert!(size > 0, "dial needs at least one position");
+        assert!(
+            std::iter::once(&start)
+                .chain(&watched)
+                .all(|p| (0..size).contains(p)),
+            "position outside of the dial"
+        );
+        Self {
+            size,
+            position: start,
+            watched,
         }
-        .rem_euclid(100);
+    }
+
+    pub fn position(&self) -> i32 {
+        self.position
+    }
+
+    /// How many of the clicks of `rot`, starting from the current position,
+    /// land on `target`.
+    fn crossings(&self, target: i32, rot: Rotation) -> i32 {
+        let (distance, count) = match rot {
+            Rotation::Left(count) => (self.position - target, count),
+            Rotation::Right(count) => (target - self.position, count),
+        };
+        // Clicks until the first time `target` is reached, a full turn if
+        // the dial is already on it.
+        let first = match distance.rem_euclid(self.size) {
+            0 => self.size,
+            first => first,
+        };
+        if count < first {
+            0
+        } else {
+            (count - first) / self.size + 1
+        }
+    }
+
+    pub fn rotate(&mut self, rot: Rotation) -> Step {
+        let crossings = self
+            .watched
+            .iter()
+            .map(|&target| self.crossings(target, rot))
+            .collect();
+        // Widened so that a dial near `i32::MAX` positions cannot overflow.
+        let (position, size) = (self.position as i64, self.size as i64);
+        let position = match rot {
+            Rotation::Left(count) => position - count as i64,
+            Rotation::Right(count) => position + count as i64,
+        };
+        self.position = position.rem_euclid(size) as i32;
+        Step {
+            position: self.position,
+            crossings,
+        }
+    }
+
+    /// The step after each of `rotations`, applied in order.
+    pub fn trace(&mut self, rotations: &[Rotation]) -> Vec<Step> {
+        rotations.iter().map(|&rot| self.rotate(rot)).collect()
     }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
-enum Rotation {
+pub enum Rotation {
     Left(i32),
     Right(i32),
 }
 
 impl FromStr for Rotation {
-    type Err = String;
+    type Err = ParseReport;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut s = s;
-        let direction = one_of(['L', 'R'])
-            .parse_next(&mut s)
-            .map_err(|_: InputError<&str>| "Could not parse direction".to_string())?;
-        let count = s[..]
-            .parse::<i32>()
-            .map_err(|_| "Could not parse count".to_owned())?;
-        match direction {
-            'L' => Ok(Self::Left(count)),
-            'R' => Ok(Self::Right(count)),
-            _ => unreachable!(),
-        }
+        aoc_utils::parse::parse_all(parse::rotation, s)
     }
 }
 
-fn calc_pass_zero_count(dial: i32, rot: Rotation) -> i32 {
-    use Rotation::*;
-    match rot {
-        Left(count) => {
-            let full_rotations = count.div_euclid(100);
-            let rem = count.rem_euclid(100);
-
-            let crosses = (dial - rem <= 0 && rem != 0 && dial != 0) as i32;
-            full_rotations + crosses
-        }
-        Right(count) => {
-            let full_rotations = count.div_euclid(100);
-            let rem = count.rem_euclid(100);
-
-            let crosses = (dial + rem >= 100 && rem != 0 && dial != 0) as i32;
-            full_rotations + crosses
-        }
-    }
+pub fn process(input: &str) -> Result<Input, ParseReport> {
+    parse::parse_input(input)
 }
 
-pub fn part1(input: &str) -> i32 {
-    let mut dial = Dial::default();
-    let rotations = input
-        .lines()
-        .map(|r| Rotation::from_str(r).expect("parse line"));
-
-    let mut z_count = 0;
-    for rot in rotations {
-        dial.rotate(rot);
-        z_count += (dial.num == 0) as i32;
-    }
-    z_count
+pub fn part1(input: &Input) -> i32 {
+    let trace = Dial::default().trace(input);
+    trace.iter().filter(|step| step.position == 0).count() as i32
 }
 
-pub fn part2(input: &str) -> i32 {
-    let mut dial = Dial::default();
-    let rotations = input
-        .lines()
-        .map(|r| Rotation::from_str(r).expect("parse line"));
-
-    let mut z_count = 0;
-    for rot in rotations {
-        z_count += calc_pass_zero_count(dial.num, rot);
-        dial.rotate(rot);
-    }
-    z_count
+pub fn part2(input: &Input) -> i32 {
+    let trace = Dial::default().trace(input);
+    trace.iter().map(|step| step.crossings[0]).sum()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc_utils::aoc_test;
+    use proptest::prelude::*;
     use rstest::*;
 
+    const SAMPLE_INPUT: &str = "L68
+L30
+R48
+L5
+R60
+L55
+L1
+L99
+R14
+L82
+";
+
+    aoc_test!(day01, example = SAMPLE_INPUT, part1 = 3, part2 = 6);
+
     #[rstest]
     fn test_rotation_from_string() {
         let rotation = Rotation::from_str("L12").unwrap();
@@ -109,23 +152,10 @@ mod tests {
     }
 
     #[rstest]
-    fn test_part1() {
-        let input = [
-            "L68", "L30", "R48", "L5", "R60", "L55", "L1", "L99", "R14", "L82",
-        ]
-        .join("\n");
-        let actual = part1(&input);
-        assert_eq!(actual, 3);
-    }
-
-    #[rstest]
-    fn test_part2() {
-        let input = [
-            "L68", "L30", "R48", "L5", "R60", "L55", "L1", "L99", "R14", "L82",
-        ]
-        .join("\n");
-        let actual = part2(&input);
-        assert_eq!(actual, 6);
+    fn test_rotation_parse_error() {
+        let report = process("L12\nX3\n").unwrap_err();
+        assert_eq!((report.line, report.column), (2, 1));
+        assert_eq!(report.expected, vec!["`L`", "`R`"]);
     }
 
     #[rstest]
@@ -145,19 +175,110 @@ mod tests {
         #[case] expected: i32,
         #[case] end_dial: i32,
     ) {
-        let actual = calc_pass_zero_count(dial, rot);
+        let step = Dial::new(100, dial, vec![0]).rotate(rot);
         assert_eq!(
-            actual, expected,
-            "count did not match actual={} expected={}",
-            actual, expected
+            step.crossings,
+            vec![expected],
+            "count did not match actual={:?} expected={}",
+            step.crossings,
+            expected
         );
-
-        let mut dial = Dial { num: dial };
-        dial.rotate(rot);
         assert_eq!(
-            dial.num, end_dial,
+            step.position, end_dial,
             "dial did not match actual={} expected={}",
-            dial.num, end_dial,
+            step.position, end_dial,
         );
     }
+
+    #[rstest]
+    fn test_custom_dial() {
+        let mut dial = Dial::new(10, 0, vec![0, 5, 9]);
+        let trace = dial.trace(&[Rotation::Right(25), Rotation::Left(1), Rotation::Left(0)]);
+        let expected = vec![
+            Step {
+                position: 5,
+                crossings: vec![2, 3, 2],
+            },
+            Step {
+                position: 4,
+                crossings: vec![0, 0, 0],
+            },
+            Step {
+                position: 4,
+                crossings: vec![0, 0, 0],
+            },
+        ];
+        assert_eq!(trace, expected);
+        assert_eq!(dial.position(), 4);
+
+        let step = Dial::default().rotate(Rotation::Right(i32::MAX));
+        assert_eq!(step.position, 97);
+
+        let mut dial = Dial::new(i32::MAX, i32::MAX - 1, vec![0]);
+        let step = dial.rotate(Rotation::Right(i32::MAX - 1));
+        assert_eq!((step.position, step.crossings), (i32::MAX - 2, vec![1]));
+        let step = dial.rotate(Rotation::Left(i32::MAX - 2));
+        assert_eq!((step.position, step.crossings), (0, vec![1]));
+    }
+
+    /// Turns a dial of `size` one click at a time, counting the clicks that
+    /// land on `target`.
+    fn naive_crossings(size: i32, dial: i32, target: i32, rot: Rotation) -> i32 {
+        let (step, count) = match rot {
+            Rotation::Left(count) => (-1, count),
+            Rotation::Right(count) => (1, count),
+        };
+        let mut num = dial;
+        let mut hits = 0;
+        for _ in 0..count {
+            num = (num + step).rem_euclid(size);
+            hits += (num == target) as i32;
+        }
+        hits
+    }
+
+    fn rotation() -> impl Strategy<Value = Rotation> {
+        prop_oneof![
+            (0..1000).prop_map(Rotation::Left),
+            (0..1000).prop_map(Rotation::Right),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn crossings_match_clicks(
+            (size, dial, target) in (1..150).prop_flat_map(|size| (Just(size), 0..size, 0..size)),
+            rot in rotation(),
+        ) {
+            let step = Dial::new(size, dial, vec![target]).rotate(rot);
+            prop_assert_eq!(step.crossings, vec![naive_crossings(size, dial, target, rot)]);
+        }
+    }
+}
+
+mod parse {
+    use super::Rotation;
+    use aoc_utils::parse::{ParseReport, label, lines, parse_all, unsigned};
+    use winnow::{
+        ModalResult, Parser,
+        combinator::{alt, cut_err},
+        error::{StrContext, StrContextValue},
+    };
+
+    pub(super) fn rotation(input: &mut &str) -> ModalResult<Rotation> {
+        let direction = alt(('L', 'R'))
+            .context(StrContext::Expected(StrContextValue::CharLiteral('L')))
+            .context(StrContext::Expected(StrContextValue::CharLiteral('R')))
+            .parse_next(input)?;
+        let count =
+            cut_err(unsigned::<u32>.verify_map(|n| i32::try_from(n).ok())).parse_next(input)?;
+        Ok(match direction {
+            'L' => Rotation::Left(count),
+            _ => Rotation::Right(count),
+        })
+    }
+
+    pub fn parse_input(input: &str) -> Result<Vec<Rotation>, ParseReport> {
+        parse_all(lines(rotation.context(label("rotation"))), input)
+    }
 }
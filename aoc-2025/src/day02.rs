@@ -1,81 +1,137 @@
-use std::{collections::HashSet, ops::RangeInclusive};
+use std::fmt;
+use std::ops::RangeInclusive;
 
-use winnow::{
-    Parser, Result,
-    ascii::digit1,
-    combinator::{separated, separated_pair},
-};
+use aoc_utils::parse::{ParseReport, comma_separated, label, parse_all, range};
+use winnow::{ModalResult, Parser};
 
-type Id = u64;
+type Id = u128;
 type IdRange = RangeInclusive<Id>;
+type Input = Vec<IdRange>;
 
-fn input_parser(input: &mut &str) -> Result<Vec<IdRange>> {
-    separated(
-        1..,
-        separated_pair(digit1, '-', digit1)
-            .map(|(a, b): (&str, &str)| a.parse::<Id>().unwrap()..=b.parse::<Id>().unwrap()),
-        ',',
-    )
-    .parse_next(input)
+fn input_parser(input: &mut &str) -> ModalResult<Vec<IdRange>> {
+    comma_separated(range.context(label("id range"))).parse_next(input)
 }
 
-fn lshift(num: Id, n: u32) -> Id {
-    num * Id::pow(10, n)
+fn digits(id: Id) -> u32 {
+    id.checked_ilog10().map_or(1, |log| log + 1)
 }
 
-fn invalid_ids(range: IdRange, part_1: bool) -> Vec<Id> {
-    let mut invalid_set = HashSet::new();
-
-    let end = range.end();
-    let max_len = end.ilog10();
-
-    for lead_len in 1..=max_len / 2 + 1 {
-        let lead_start = Id::pow(10, lead_len - 1);
-        let lead_end = Id::pow(10, lead_len);
-        for lead in lead_start..lead_end {
-            // Can probably make it more efficient by making it the right lenght
-            // from the start.
-            let mut new_id = lead;
-            loop {
-                new_id = lshift(new_id, lead_len) + lead;
-                if range.contains(&new_id) {
-                    invalid_set.insert(new_id);
-                } else if part_1 || new_id > *end {
-                    break;
-                }
+/// The Möbius function, `0` unless `n` is square-free and otherwise `-1` to
+/// the number of its prime factors.
+fn mobius(mut n: u32) -> i32 {
+    let mut sign = 1;
+    let mut p = 2;
+    while p * p <= n {
+        if n.is_multiple_of(p) {
+            n /= p;
+            if n.is_multiple_of(p) {
+                return 0;
             }
+            sign = -sign;
         }
+        p += 1;
     }
-    invalid_set.into_iter().collect()
+    if n > 1 { -sign } else { sign }
 }
 
-pub fn part1(input: &str) -> Id {
-    let mut input = input;
-    let input = input_parser.parse_next(&mut input).unwrap();
-    let mut sum = 0;
-    for range in input {
-        let inv = invalid_ids(range, true);
-        sum += inv.into_iter().sum::<Id>();
+/// Sum of the ids in `range` with `len` digits that are a block of
+/// `block_len` digits repeated, where `block_len` divides `len`.
+///
+/// Those ids are `block * (1 + 10^block_len + 10^(2 block_len) + ...)` for
+/// every `block` of exactly `block_len` digits, so the blocks in the range
+/// form an interval and their sum is an arithmetic series.
+fn repeated_sum(range: &IdRange, len: u32, block_len: u32) -> Option<Id> {
+    let multiplier: Id = (0..len / block_len)
+        .map(|i| Id::pow(10, i * block_len))
+        .sum();
+    let first = Id::pow(10, block_len - 1).max(range.start().div_ceil(multiplier));
+    let last = (Id::pow(10, block_len) - 1).min(range.end() / multiplier);
+    if first > last {
+        return Some(0);
     }
-    sum
+    // One of the two factors is even, and neither overflows as blocks have at
+    // most 19 digits.
+    let block_sum = (first + last) * (last - first + 1) / 2;
+    block_sum.checked_mul(multiplier)
 }
 
-pub fn part2(input: &str) -> Id {
-    let mut input = input;
-    let input = input_parser.parse_next(&mut input).unwrap();
-    let mut sum = 0;
-    for range in input {
-        let inv = invalid_ids(range, false);
-        sum += inv.into_iter().sum::<Id>();
+/// Sum of the invalid ids in `range`, `None` if it does not fit in an `Id`.
+///
+/// For part 2 the ids of each length are a union over the block lengths
+/// dividing it, and the repeats of a block length are also repeats of all its
+/// multiples. Inclusion–exclusion over the divisors therefore weighs the sum
+/// for each block length by the Möbius function of the number of repeats.
+fn invalid_sum(range: &IdRange, part_1: bool) -> Option<Id> {
+    let (mut added, mut removed): (Id, Id) = (0, 0);
+    for len in digits(*range.start())..=digits(*range.end()) {
+        if part_1 {
+            if len.is_multiple_of(2) {
+                added = added.checked_add(repeated_sum(range, len, len / 2)?)?;
+            }
+            continue;
+        }
+        for block_len in (1..len).filter(|&b| len.is_multiple_of(b)) {
+            let sum = match mobius(len / block_len) {
+                0 => continue,
+                1 => &mut removed,
+                _ => &mut added,
+            };
+            *sum = sum.checked_add(repeated_sum(range, len, block_len)?)?;
+        }
     }
-    sum
+    Some(added - removed)
+}
+
+/// The sum of the invalid ids does not fit in an `Id`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Overflow;
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the sum of invalid ids overflows u128")
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+fn total_invalid_sum(input: &Input, part_1: bool) -> Result<Id, Overflow> {
+    input
+        .iter()
+        .try_fold(0, |sum: Id, range| {
+            sum.checked_add(invalid_sum(range, part_1)?)
+        })
+        .ok_or(Overflow)
+}
+
+pub fn process(input: &str) -> Result<Input, ParseReport> {
+    parse_all(input_parser, input)
+}
+
+pub fn part1(input: &Input) -> Result<Id, Overflow> {
+    total_invalid_sum(input, true)
+}
+
+pub fn part2(input: &Input) -> Result<Id, Overflow> {
+    total_invalid_sum(input, false)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc_utils::aoc_test;
     use itertools::Itertools;
+    use proptest::prelude::*;
     use rstest::*;
+    use std::collections::HashSet;
+
+    const SAMPLE_INPUT: &str = "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,1698522-1698528,446443-446449,38593856-38593862,565653-565659,824824821-824824827,2121212118-2121212124";
+
+    aoc_test!(
+        day02,
+        example = SAMPLE_INPUT,
+        part1 = Ok(1227775554),
+        part2 = Ok(4174379265),
+    );
 
     #[rstest]
     fn test_parse_input() {
@@ -84,6 +140,49 @@ mod tests {
         assert_eq!(parsed, vec![11..=22, 95..=115]);
     }
 
+    #[rstest]
+    fn test_parse_error() {
+        let report = process("11-22,95-x").unwrap_err();
+        assert_eq!((report.line, report.column), (1, 10));
+        assert_eq!(report.labels, vec!["id range"]);
+    }
+
+    #[rstest]
+    fn test_overflow() {
+        let input = process(&format!("1-{}", Id::MAX)).unwrap();
+        assert_eq!(part1(&input), Err(Overflow));
+        assert_eq!(part2(&input), Err(Overflow));
+    }
+
+    fn lshift(num: Id, n: u32) -> Id {
+        num * Id::pow(10, n)
+    }
+
+    /// Enumerates every repeated block number up to the end of `range`.
+    fn invalid_ids(range: IdRange, part_1: bool) -> Vec<Id> {
+        let mut invalid_set = HashSet::new();
+
+        let end = range.end();
+        let max_len = end.ilog10();
+
+        for lead_len in 1..=max_len / 2 + 1 {
+            let lead_start = Id::pow(10, lead_len - 1);
+            let lead_end = Id::pow(10, lead_len);
+            for lead in lead_start..lead_end {
+                let mut new_id = lead;
+                loop {
+                    new_id = lshift(new_id, lead_len) + lead;
+                    if range.contains(&new_id) {
+                        invalid_set.insert(new_id);
+                    } else if part_1 || new_id > *end {
+                        break;
+                    }
+                }
+            }
+        }
+        invalid_set.into_iter().collect()
+    }
+
     #[rstest]
     #[case(11..=22, vec![11, 22])]
     #[case(95..=115, vec![99])]
@@ -94,6 +193,7 @@ mod tests {
     #[case(446443..=446449, vec![446446])]
     #[case(38593856..=38593862, vec![38593859])]
     fn test_invalid_ids_part1(#[case] range: IdRange, #[case] expected: Vec<Id>) {
+        assert_eq!(invalid_sum(&range, true), Some(expected.iter().sum()));
         let actual = invalid_ids(range, true);
         assert!(
             actual.iter().sorted().eq(expected.iter().sorted()),
@@ -116,6 +216,7 @@ mod tests {
     #[case(824824821..=824824827, vec![824824824])]
     #[case(2121212118..=2121212124, vec![2121212121])]
     fn test_invalid_ids_part2(#[case] range: IdRange, #[case] expected: Vec<Id>) {
+        assert_eq!(invalid_sum(&range, false), Some(expected.iter().sum()));
         let actual = invalid_ids(range, false);
         assert!(
             actual.iter().sorted().eq(expected.iter().sorted()),
@@ -126,10 +227,53 @@ mod tests {
     }
 
     #[rstest]
-    fn test_part_1() {
-        let expected = 1227775554;
-        let input = "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,1698522-1698528,446443-446449,38593856-38593862,565653-565659,824824821-824824827,2121212118-2121212124";
-        let actual = part1(input);
-        assert_eq!(actual, expected);
+    fn test_mobius() {
+        let actual: Vec<_> = (1..=12).map(mobius).collect();
+        assert_eq!(actual, vec![1, -1, -1, 0, -1, 1, -1, 0, 0, 1, -1, 0]);
+    }
+
+    #[rstest]
+    fn test_wide_ranges() {
+        // Only the 2 digit ids with both digits equal are invalid.
+        assert_eq!(invalid_sum(&(0..=99), false), Some(11 * 45));
+        // Blocks of 10 digits from 1000000000 to 1844674407, repeated twice.
+        let range = Id::pow(10, 19)..=u64::MAX as Id;
+        assert_eq!(
+            invalid_sum(&range, true),
+            Some(12_014_118_354_628_792_115_342_738_028)
+        );
+        // A block of 13 digits repeated 3 times is the largest invalid id.
+        let largest = 340_282_366_920_934_028_236_692_093_402_823_669_209;
+        let step = 100_000_000_000_010_000_000_000_001;
+        assert_eq!(
+            invalid_sum(&(largest - step + 1..=Id::MAX), false),
+            Some(largest)
+        );
+        assert_eq!(invalid_sum(&(largest - step..=Id::MAX), false), None);
+    }
+
+    /// Whether `id` is some digit sequence repeated, exactly twice for part 1.
+    fn is_invalid(id: Id, part_1: bool) -> bool {
+        let digits = id.to_string();
+        let len = digits.len();
+        (1..=len / 2)
+            .filter(|lead_len| len.is_multiple_of(*lead_len) && (!part_1 || len == 2 * lead_len))
+            .any(|lead_len| digits.as_bytes().chunks(lead_len).all_equal())
+    }
+
+    proptest! {
+        #[test]
+        fn invalid_ids_match_scan(
+            start in 1..10_000_000 as Id,
+            len in 0..2000 as Id,
+            part_1: bool,
+        ) {
+            let range = start..=start + len;
+            let expected: Vec<Id> = range.clone().filter(|&id| is_invalid(id, part_1)).collect();
+            let sum = expected.iter().sum();
+            let actual: Vec<Id> = invalid_ids(range.clone(), part_1).into_iter().sorted().collect();
+            prop_assert_eq!(actual, expected);
+            prop_assert_eq!(invalid_sum(&range, part_1), Some(sum));
+        }
     }
 }
@@ -1,153 +1,196 @@
 use std::collections::VecDeque;
 
-#[derive(Clone, Debug, PartialEq)]
-struct BatteryBank {
-    bank: Vec<u8>,
-    active: VecDeque<usize>,
-}
+use aoc_utils::bigint::BigUint;
+use aoc_utils::parse::ParseReport;
 
-struct BankBuilder<C> {
-    capacity: C,
-}
+type Input = Vec<Vec<u8>>;
 
-impl Default for BankBuilder<usize> {
-    fn default() -> Self {
-        BankBuilder { capacity: 2 }
-    }
+/// The batteries turned on in a bank.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Selection {
+    /// Positions of the batteries in the bank, in increasing order.
+    pub indices: Vec<usize>,
+    pub joltage: BigUint,
 }
 
-impl<C> BankBuilder<C> {
-    fn with_capacity(self, capacity: usize) -> BankBuilder<usize> {
-        BankBuilder { capacity }
+/// Adds the battery at `idx` to the best selection so far, where
+/// `remaining` batteries, this one included, are left in the bank.
+///
+/// It replaces the smaller digits before it for as long as enough batteries
+/// remain to fill the selection.
+fn settle(
+    stack: &mut Vec<(usize, u8)>,
+    (idx, digit): (usize, u8),
+    remaining: usize,
+    capacity: usize,
+) {
+    while stack.last().is_some_and(|&(_, top)| top < digit)
+        && stack.len() - 1 + remaining >= capacity
+    {
+        stack.pop();
+    }
+    if stack.len() < capacity {
+        stack.push((idx, digit));
     }
 }
 
-impl BankBuilder<usize> {
-    fn build_from_str(&self, bank: &str) -> BatteryBank {
-        let bank = bank
-            .chars()
-            .map(|c| c.to_digit(10).unwrap() as u8)
-            .collect();
-        BatteryBank {
-            bank,
-            active: (0..self.capacity).collect(),
+/// Turns on the `capacity` batteries of a bank that give the largest
+/// joltage, or all of them if there are fewer, preferring earlier batteries
+/// on ties.
+///
+/// The digits are read once, keeping a stack of the best selection so far.
+/// A digit is only settled onto it once `capacity` more have been read, or at
+/// the end of the bank, so the bank's length need not be known up front and
+/// only `capacity` digits are held back.
+pub fn select_batteries(bank: impl IntoIterator<Item = u8>, capacity: usize) -> Selection {
+    let mut stack: Vec<(usize, u8)> = Vec::with_capacity(capacity);
+    let mut window = VecDeque::with_capacity(capacity + 1);
+    for battery in bank.into_iter().enumerate() {
+        window.push_back(battery);
+        if window.len() > capacity {
+            let battery = window.pop_front().expect("window is not empty");
+            settle(&mut stack, battery, capacity + 1, capacity);
         }
     }
+    let len = window.len();
+    for (i, battery) in window.into_iter().enumerate() {
+        settle(&mut stack, battery, len - i, capacity);
+    }
+    Selection {
+        indices: stack.iter().map(|&(idx, _)| idx).collect(),
+        joltage: BigUint::from_decimal_digits(stack.iter().map(|&(_, digit)| digit)),
+    }
 }
 
-fn parse_input(input: &str, builder: &BankBuilder<usize>) -> Vec<BatteryBank> {
+fn total_joltage(input: &Input, capacity: usize) -> BigUint {
     input
-        .lines()
-        .filter_map(|l| {
-            if l.is_empty() {
-                None
-            } else {
-                Some(builder.build_from_str(l))
-            }
-        })
-        .collect()
-}
-
-impl BatteryBank {
-    fn joltage(&self) -> u128 {
-        self.active
-            .iter()
-            .fold(0u128, |acc, idx| acc * 10 + self.bank[*idx] as u128)
-    }
+        .iter()
+        .map(|bank| select_batteries(bank.iter().copied(), capacity).joltage)
+        .sum()
 }
 
-fn select_batteries(bank: BatteryBank) -> BatteryBank {
-    let capacity = bank.active.len();
-    let total = bank.bank.len();
-
-    let mut active = VecDeque::new();
-    for active_idx in 0..capacity {
-        let options = match active.back() {
-            Some(&i) => i + 1..=total - capacity + active_idx,
-            None => 0..=total - capacity + active_idx,
-        };
-        let idx = options.rev().max_by_key(|&i| bank.bank[i]).unwrap();
-        active.push_back(idx);
-    }
-    BatteryBank {
-        bank: bank.bank,
-        active,
-    }
+pub fn process(input: &str) -> Result<Input, ParseReport> {
+    parse::parse_input(input)
 }
 
-pub fn part1(input: &str) -> u128 {
-    let builder = BankBuilder::default();
-    let parsed = parse_input(input, &builder);
-    parsed
-        .into_iter()
-        .map(|b| select_batteries(b).joltage())
-        .sum()
+pub fn part1(input: &Input) -> BigUint {
+    total_joltage(input, 2)
 }
 
-pub fn part2(input: &str) -> u128 {
-    let builder = BankBuilder::default().with_capacity(12);
-    let parsed = parse_input(input, &builder);
-    parsed
-        .into_iter()
-        .map(|b| select_batteries(b).joltage())
-        .sum()
+pub fn part2(input: &Input) -> BigUint {
+    total_joltage(input, 12)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc_utils::aoc_test;
+    use itertools::Itertools;
+    use proptest::prelude::*;
     use rstest::*;
 
+    const SAMPLE_INPUT: &str = "987654321111111
+811111111111119
+234234234234278
+818181911112111";
+
+    aoc_test!(
+        day03,
+        example = SAMPLE_INPUT,
+        part1 = BigUint::from(357u64),
+        part2 = BigUint::from(3121910778619u64),
+    );
+
     #[rstest]
     fn test_parse_input() {
-        let bank_builder = BankBuilder::default().with_capacity(2);
         let input = "12\n34";
-        let parsed = parse_input(input, &bank_builder);
-        assert_eq!(
-            parsed,
-            vec![
-                BatteryBank {
-                    bank: vec![1, 2],
-                    active: vec![0, 1].into_iter().collect(),
-                },
-                BatteryBank {
-                    bank: vec![3, 4],
-                    active: vec![0, 1].into_iter().collect(),
-                }
-            ]
-        );
+        let parsed = process(input).unwrap();
+        assert_eq!(parsed, vec![vec![1, 2], vec![3, 4]]);
     }
 
     #[rstest]
-    fn test_joltage() {
-        let bank = BatteryBank {
-            bank: vec![1, 2, 3],
-            active: vec![0, 2].into_iter().collect(),
-        };
-        assert_eq!(bank.joltage(), 13);
+    #[case(&[9, 8, 7, 6, 5, 4, 3, 2, 1, 1, 1, 1, 1, 1, 1], 2, vec![0, 1])]
+    #[case(&[8, 1, 1, 1, 9], 2, vec![0, 4])]
+    #[case(&[2, 3, 4, 2, 3, 4, 2, 7, 8], 3, vec![2, 7, 8])]
+    #[case(&[5, 5, 5], 2, vec![0, 1])]
+    #[case(&[1, 2], 3, vec![0, 1])]
+    fn test_selected_indices(
+        #[case] bank: &[u8],
+        #[case] capacity: usize,
+        #[case] expected: Vec<usize>,
+    ) {
+        let selection = select_batteries(bank.iter().copied(), capacity);
+        assert_eq!(selection.indices, expected);
     }
 
     #[rstest]
-    fn test_part1() {
-        let input = "987654321111111
-811111111111119
-234234234234278
-818181911112111";
+    fn test_streaming_bank() {
+        let bank = "818181911112111"
+            .chars()
+            .map(|c| c.to_digit(10).unwrap() as u8);
+        let selection = select_batteries(bank, 12);
+        assert_eq!(selection.joltage, BigUint::from(888911112111u64));
 
-        let expected = 357;
-        let actual = part1(input);
-        assert_eq!(actual, expected, "{} {}", expected, actual);
+        let bank = std::iter::successors(Some(1u8), |&d| (d < 9).then_some(d + 1));
+        let selection = select_batteries(bank, 3);
+        assert_eq!(selection.indices, vec![6, 7, 8]);
     }
 
     #[rstest]
-    fn test_part2() {
-        let input = "987654321111111
-811111111111119
-234234234234278
-818181911112111";
+    fn test_parse_error() {
+        let report = process("12\n3x4\n").unwrap_err();
+        assert_eq!((report.line, report.column), (2, 2));
+    }
+
+    #[rstest]
+    fn test_long_selection() {
+        let bank = vec![7; 100];
+        let selection = select_batteries(bank, 50);
+        assert_eq!(selection.indices, (0..50).collect::<Vec<_>>());
+        assert_eq!(selection.joltage.to_string(), "7".repeat(50));
+    }
+
+    /// The best joltage over every choice of `capacity` batteries.
+    fn naive_joltage(bank: &[u8], capacity: usize) -> u128 {
+        (0..bank.len())
+            .combinations(capacity)
+            .map(|active| {
+                active
+                    .iter()
+                    .fold(0u128, |acc, &idx| acc * 10 + bank[idx] as u128)
+            })
+            .max()
+            .unwrap()
+    }
+
+    fn bank_and_capacity() -> impl Strategy<Value = (Vec<u8>, usize)> {
+        prop::collection::vec(1..=9u8, 1..=14).prop_flat_map(|bank| {
+            let len = bank.len();
+            (Just(bank), 1..=len)
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn select_batteries_matches_subsets((bank, capacity) in bank_and_capacity()) {
+            let selected = select_batteries(bank.iter().copied(), capacity);
+            prop_assert_eq!(selected.joltage, BigUint::from(naive_joltage(&bank, capacity)));
+        }
+    }
+}
+
+mod parse {
+    use aoc_utils::parse::{ParseReport, expected, grid, label, parse_all};
+    use winnow::{ModalResult, Parser, token::one_of};
+
+    fn digit(input: &mut &str) -> ModalResult<u8> {
+        one_of('0'..='9')
+            .map(|c: char| c as u8 - b'0')
+            .context(expected("battery joltage digit"))
+            .parse_next(input)
+    }
 
-        let expected = 3121910778619;
-        let actual = part2(input);
-        assert_eq!(actual, expected, "{} {}", expected, actual);
+    pub fn parse_input(input: &str) -> Result<Vec<Vec<u8>>, ParseReport> {
+        parse_all(grid(digit).context(label("battery banks")), input)
     }
 }
@@ -1,18 +1,22 @@
-type Input = (Vec<Position>, Grid<u8>);
-
-const SURROUNDING: [Position; 8] = [
-    Position(-1, 1),
-    Position(0, 1),
-    Position(1, 1),
-    Position(-1, 0),
-    Position(1, 0),
-    Position(-1, -1),
-    Position(0, -1),
-    Position(1, -1),
-];
-
-#[derive(Copy, Clone, Debug)]
-pub struct Position(isize, isize);
+use std::str::FromStr;
+
+use aoc_utils::parse::ParseReport;
+use aoc_utils::visualize::{Frame, Recorder, Rgb};
+
+type Input = Timeline;
+
+aoc_utils::params! {
+    pub struct Params {
+        /// A roll can be removed while fewer of its neighbours are rolls.
+        pub threshold: usize = 4,
+        /// Which cells around a roll are its neighbours.
+        pub connectivity: Connectivity = Connectivity::Eight,
+    }
+}
+
+/// Row and column of a cell.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Position(pub isize, pub isize);
 
 impl std::ops::Add for Position {
     type Output = Self;
@@ -22,7 +26,46 @@ impl std::ops::Add for Position {
     }
 }
 
-#[derive(Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Connectivity {
+    /// The cells above, below, left and right.
+    Four,
+    /// Also the diagonal cells.
+    Eight,
+}
+
+impl Connectivity {
+    fn offsets(self) -> &'static [Position] {
+        const SURROUNDING: [Position; 8] = [
+            Position(-1, 0),
+            Position(0, -1),
+            Position(0, 1),
+            Position(1, 0),
+            Position(-1, -1),
+            Position(-1, 1),
+            Position(1, -1),
+            Position(1, 1),
+        ];
+        match self {
+            Self::Four => &SURROUNDING[..4],
+            Self::Eight => &SURROUNDING,
+        }
+    }
+}
+
+impl FromStr for Connectivity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "4" => Ok(Self::Four),
+            "8" => Ok(Self::Eight),
+            _ => Err("expected 4 or 8".to_owned()),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct Grid<T> {
     items: Vec<T>,
     width: usize,
@@ -49,78 +92,113 @@ impl<T> std::ops::IndexMut<Position> for Grid<T> {
     }
 }
 
-pub fn process(input: &str) -> Input {
-    let mut lines = input.lines();
-    let first_line = lines.next().expect("No first line");
-    let items = lines.filter(|line| !line.is_empty()).fold(
-        Vec::from_iter(first_line.bytes()),
-        |mut acc, line| {
-            acc.extend(line.bytes());
-            acc
-        },
-    );
+/// The order in which the rolls can be removed.
+#[derive(Clone, Debug)]
+pub struct Timeline {
+    pub width: usize,
+    pub height: usize,
+    /// The rolls removed in each round, where a round removes every roll that
+    /// is accessible once the earlier rounds are removed.
+    pub waves: Vec<Vec<Position>>,
+    /// The rolls that can never be removed.
+    pub core: Vec<Position>,
+}
+
+pub fn process(input: &str, params: &Params) -> Result<Input, ParseReport> {
+    let rows = parse::parse_input(input)?;
+    let width = rows[0].len();
+    let height = rows.len();
+    let offsets = params.connectivity.offsets();
+    let positions =
+        (0..height as isize).flat_map(|i| (0..width as isize).map(move |j| Position(i, j)));
 
-    let width = first_line.len();
-    let height = items.len() / width;
-    let in_grid = Grid {
-        items,
+    let rolls = Grid {
+        items: rows.concat(),
         width,
         height,
     };
-    let mut grid = Grid {
-        items: vec![u8::MAX; width * height],
+    let neighbours = |pos: Position| {
+        offsets
+            .iter()
+            .map(move |&p| pos + p)
+            .filter(|&n| rolls.contains(n) && rolls[n] == b'@')
+    };
+
+    // Neighbouring rolls still standing, `None` for empty or removed cells.
+    let mut counts = Grid {
+        items: vec![None; width * height],
         width,
         height,
     };
+    let mut wave = vec![];
+    for pos in positions.clone().filter(|&pos| rolls[pos] == b'@') {
+        let count = neighbours(pos).count();
+        if count < params.threshold {
+            wave.push(pos);
+        }
+        counts[pos] = Some(count);
+    }
 
-    let mut to_remove = Vec::with_capacity(width * height);
-    for i in 0..width as isize {
-        for j in 0..height as isize {
-            let pos = Position(i, j);
-
-            if in_grid[pos] == b'@' {
-                let n_count = SURROUNDING
-                    .iter()
-                    .map(|&p| p + pos)
-                    .filter(|&n| in_grid.contains(n) && in_grid[n] == b'@')
-                    .count() as u8;
-                if n_count < 4 {
-                    to_remove.push(pos);
+    let mut waves = vec![];
+    while !wave.is_empty() {
+        for &pos in &wave {
+            counts[pos] = None;
+        }
+        let mut next = vec![];
+        for &pos in &wave {
+            for n in neighbours(pos) {
+                if let Some(count) = &mut counts[n] {
+                    // Rolls below the threshold are already in this wave.
+                    if *count == params.threshold {
+                        next.push(n);
+                    }
+                    *count -= 1;
                 }
-                grid[pos] = n_count;
             }
         }
+        waves.push(std::mem::replace(&mut wave, next));
     }
-    (to_remove, grid)
+
+    let core = positions.filter(|&pos| counts[pos].is_some()).collect();
+    Ok(Timeline {
+        width,
+        height,
+        waves,
+        core,
+    })
 }
 
 pub fn part1(input: &Input) -> u64 {
-    input.0.len() as u64
+    input.waves.first().map_or(0, Vec::len) as u64
 }
 
 pub fn part2(input: &Input) -> u64 {
-    let (mut to_remove, mut grid) = input.clone();
-
-    let mut removed = 0;
-    while let Some(pos) = to_remove.pop() {
-        removed += 1;
+    input.waves.iter().map(Vec::len).sum::<usize>() as u64
+}
 
-        for next in SURROUNDING.iter().map(|&p| pos + p) {
-            if grid.contains(next) {
-                if grid[next] == 4 {
-                    to_remove.push(next);
-                }
+/// Draws the rolls still standing in white and the removed ones in red, one
+/// frame per wave of removals.
+pub fn visualize(input: &Input, recorder: &mut Recorder) -> std::io::Result<()> {
+    let mut frame = Frame::new(input.width, input.height, Rgb::BLACK);
+    let rolls = input.waves.iter().flatten().chain(&input.core);
+    for pos in rolls {
+        frame.set(pos.1 as usize, pos.0 as usize, Rgb::WHITE);
+    }
+    recorder.record(&frame)?;
 
-                grid[next] -= 1;
-            }
+    for wave in &input.waves {
+        for pos in wave {
+            frame.set(pos.1 as usize, pos.0 as usize, Rgb::RED);
         }
+        recorder.record(&frame)?;
     }
-    removed
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc_utils::aoc_test;
     use rstest::rstest;
 
     const SAMPLE_INPUT: &str = "..@@.@@@@.
@@ -134,20 +212,106 @@ mod tests {
 .@@@@@@@@.
 @.@.@@@.@.";
 
+    aoc_test!(
+        day04,
+        example = SAMPLE_INPUT,
+        part1 = 13,
+        part2 = 43,
+        params = {},
+    );
+
+    fn timeline(input: &str, threshold: usize, connectivity: Connectivity) -> Timeline {
+        let params = Params {
+            threshold,
+            connectivity,
+        };
+        process(input, &params).unwrap()
+    }
+
     #[rstest]
-    fn test_part1() {
-        let input = process(SAMPLE_INPUT);
-        let expected = 13;
-        let actual = part1(&input);
-        assert_eq!(actual, expected, "expected={}, actual={}", expected, actual)
+    fn test_non_square() {
+        let input = "@@@\n@@@\n";
+        let actual = timeline(input, 4, Connectivity::Eight);
+        let corners = vec![
+            Position(0, 0),
+            Position(0, 2),
+            Position(1, 0),
+            Position(1, 2),
+        ];
+        assert_eq!(
+            actual.waves,
+            vec![corners, vec![Position(0, 1), Position(1, 1)]]
+        );
+        assert!(actual.core.is_empty());
+
+        let actual = timeline(input, 2, Connectivity::Four);
+        assert!(actual.waves.is_empty());
+        assert_eq!(actual.core.len(), 6);
+
+        let input = "@.\n@@\n@@\n@.\n";
+        let actual = timeline(input, 2, Connectivity::Four);
+        assert_eq!(actual.waves, vec![vec![Position(0, 0), Position(3, 0)]]);
+        let core = vec![
+            Position(1, 0),
+            Position(1, 1),
+            Position(2, 0),
+            Position(2, 1),
+        ];
+        assert_eq!(actual.core, core);
     }
 
     #[rstest]
-    fn test_part2() {
-        let input = process(SAMPLE_INPUT);
+    fn test_waves() {
+        let actual = timeline(SAMPLE_INPUT, 4, Connectivity::Eight);
+        let sizes: Vec<_> = actual.waves.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![13, 12, 7, 5, 2, 1, 1, 1, 1]);
+        assert_eq!(actual.core.len(), 71 - 43);
+        assert_eq!("4".parse(), Ok(Connectivity::Four));
+        assert!("6".parse::<Connectivity>().is_err());
+    }
 
-        let expected = 43;
-        let actual = part2(&input);
-        assert_eq!(actual, expected, "expected={}, actual={}", expected, actual)
+    #[rstest]
+    fn test_parse_error() {
+        let params = Params::default();
+        let report = process("..@\n.#@\n", &params).unwrap_err();
+        assert_eq!((report.line, report.column), (2, 2));
+        assert_eq!(report.expected, vec!["`@`", "`.`"]);
+
+        let report = process("..@\n.@\n", &params).unwrap_err();
+        assert_eq!((report.line, report.column), (2, 1));
+    }
+}
+
+mod parse {
+    use aoc_utils::parse::{ParseReport, label, parse_all, rect_grid};
+    use winnow::{
+        ModalResult, Parser,
+        combinator::alt,
+        error::{StrContext, StrContextValue},
+    };
+
+    fn cell(input: &mut &str) -> ModalResult<u8> {
+        alt(('@'.value(b'@'), '.'.value(b'.')))
+            .context(StrContext::Expected(StrContextValue::CharLiteral('@')))
+            .context(StrContext::Expected(StrContextValue::CharLiteral('.')))
+            .parse_next(input)
+    }
+
+    pub fn parse_input(input: &str) -> Result<Vec<Vec<u8>>, ParseReport> {
+        parse_all(rect_grid(cell).context(label("paper roll grid")), input)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_non_square() {
+            let actual = parse_input("@@.@@\n.@@@.\n").unwrap();
+            assert_eq!(actual, vec![b"@@.@@".to_vec(), b".@@@.".to_vec()]);
+
+            let actual = parse_input("@\n.\n@\n").unwrap();
+            assert_eq!(actual, vec![b"@".to_vec(), b".".to_vec(), b"@".to_vec()]);
+        }
     }
 }
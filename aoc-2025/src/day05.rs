@@ -1,18 +1,27 @@
+use std::ops::RangeInclusive;
+
+use aoc_utils::parse::ParseReport;
+
 type InputType = u128;
+type Input = (Vec<RangeInclusive<InputType>>, Vec<InputType>);
 
-pub fn part1(input: &str) -> usize {
-    let (ranges, ids) = parse::parse_input(input);
+pub fn process(input: &str) -> Result<Input, ParseReport> {
+    parse::parse_input(input)
+}
 
-    ids.into_iter()
+pub fn part1(input: &Input) -> usize {
+    let (ranges, ids) = input;
+
+    ids.iter()
+        .copied()
         .filter(|&id| ranges.iter().filter(|range| range.contains(&id)).count() > 0)
         .count()
 }
 
-pub fn part2(input: &str) -> InputType {
-    let (ranges, _) = parse::parse_input(input);
-
+/// The number of fresh ids, saturating when the ranges cover every id.
+pub fn part2(input: &Input) -> InputType {
     let ranges = {
-        let mut ranges = ranges;
+        let mut ranges = input.0.clone();
         ranges.sort_by_key(|range| *range.start());
         ranges
     };
@@ -21,7 +30,7 @@ pub fn part2(input: &str) -> InputType {
     let mut current_start = ranges[0].start();
     let mut current_end = ranges[0].end();
     for r in &ranges[1..] {
-        if *r.start() <= current_end + 1 {
+        if *r.start() <= current_end.saturating_add(1) {
             current_end = current_end.max(r.end());
         } else {
             total += current_end - current_start + 1;
@@ -29,19 +38,18 @@ pub fn part2(input: &str) -> InputType {
             current_end = r.end();
         }
     }
-    total += current_end - current_start + 1;
-    total
+    total.saturating_add((current_end - current_start).saturating_add(1))
 }
 
 #[cfg(test)]
 mod tests {
-
     use super::*;
+    use aoc_utils::aoc_test;
+    use proptest::prelude::*;
     use rstest::*;
+    use std::collections::HashSet;
 
-    #[rstest]
-    fn test_part1() {
-        let input = "3-5
+    const SAMPLE_INPUT: &str = "3-5
 10-14
 16-20
 12-18
@@ -52,79 +60,76 @@ mod tests {
 11
 17
 32";
-        let expected = 3;
-        let actual = part1(input);
-        assert_eq!(actual, expected);
+
+    aoc_test!(day05, example = SAMPLE_INPUT, part1 = 3, part2 = 14);
+
+    #[rstest]
+    fn test_parse_error() {
+        let report = process("3-5\n10-x\n\n1\n").unwrap_err();
+        assert_eq!((report.line, report.column), (2, 4));
+        assert_eq!(report.labels, vec!["fresh ranges"]);
+
+        let report = process("3-5\n5-3\n\n1\n").unwrap_err();
+        assert_eq!((report.line, report.column), (2, 1));
     }
 
     #[rstest]
-    fn test_part2() {
-        let input = "3-5
-10-14
-16-20
-12-18
+    fn test_part2_near_max() {
+        let max = InputType::MAX;
+        assert_eq!(part2(&(vec![max - 2..=max, 5..=max - 3], vec![])), max - 4);
+        assert_eq!(part2(&(vec![0..=max, 7..=9], vec![])), max);
+    }
 
-1
-5
-8
-11
-17
-32";
-        let expected = 14;
-        let actual = part2(input);
-        assert_eq!(actual, expected);
+    fn ranges() -> impl Strategy<Value = Vec<RangeInclusive<InputType>>> {
+        prop::collection::vec(
+            (0..200 as InputType, 0..30 as InputType).prop_map(|(start, len)| start..=start + len),
+            1..20,
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn merged_ranges_match_fresh_ids(ranges in ranges()) {
+            let fresh: HashSet<InputType> = ranges.iter().cloned().flatten().collect();
+            prop_assert_eq!(part2(&(ranges, vec![])), fresh.len() as InputType);
+        }
     }
 }
 
 mod parse {
-    use super::InputType;
+    use super::{Input, InputType};
     use std::ops::RangeInclusive;
 
-    use winnow::{
-        Parser, Result,
-        ascii::{digit1, newline},
-        combinator::{separated, separated_pair},
-        error::{StrContext, StrContextValue},
-    };
+    use aoc_utils::parse::{ParseReport, expected, label, lines, parse_all, range, unsigned};
+    use winnow::{ModalResult, Parser, combinator::separated_pair};
 
-    fn create_ctx(desc: &'static str) -> StrContext {
-        StrContext::Expected(StrContextValue::Description(desc))
-    }
-
-    fn ranges_parser(input: &mut &str) -> Result<Vec<RangeInclusive<InputType>>> {
-        separated(
-            1..,
-            separated_pair(
-                digit1.parse_to::<InputType>(),
-                "-",
-                digit1.parse_to::<InputType>(),
-            )
-            .map(|(start, end)| start..=end),
-            newline,
+    fn ranges_parser(input: &mut &str) -> ModalResult<Vec<RangeInclusive<InputType>>> {
+        lines(
+            range
+                .verify(|range: &RangeInclusive<InputType>| range.start() <= range.end())
+                .context(expected("a range that does not end before it starts")),
         )
+        .context(label("fresh ranges"))
         .parse_next(input)
     }
 
-    fn id_parser(input: &mut &str) -> Result<Vec<InputType>> {
-        separated(
-            1..,
-            digit1.parse_to::<InputType>().context(create_ctx("id")),
-            newline,
-        )
-        .context(create_ctx("all_ids"))
-        .parse_next(input)
+    fn id_parser(input: &mut &str) -> ModalResult<Vec<InputType>> {
+        lines(unsigned.context(expected("id")))
+            .context(label("available ids"))
+            .parse_next(input)
     }
 
-    fn input_parser(input: &mut &str) -> Result<(Vec<RangeInclusive<InputType>>, Vec<InputType>)> {
-        separated_pair(ranges_parser, "\n\n".context(create_ctx("del")), id_parser)
-            .context(create_ctx("full_input"))
-            .parse_next(input)
+    fn input_parser(input: &mut &str) -> ModalResult<Input> {
+        separated_pair(
+            ranges_parser,
+            "\n\n".context(expected("blank line")),
+            id_parser,
+        )
+        .parse_next(input)
     }
 
-    pub fn parse_input(input: &str) -> (Vec<RangeInclusive<InputType>>, Vec<InputType>) {
-        // input_parser.parse(input).unwrap()
-        let mut input = input;
-        input_parser.parse_next(&mut input).unwrap()
+    pub fn parse_input(input: &str) -> Result<Input, ParseReport> {
+        parse_all(input_parser, input)
     }
 
     #[cfg(test)]
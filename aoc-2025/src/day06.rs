@@ -1,164 +1,388 @@
-#[derive(Eq, PartialEq, Debug)]
-enum Operation {
+use std::fmt;
+
+use aoc_utils::parse::ParseReport;
+
+type Input = Worksheet;
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum Operator {
     Add,
+    Sub,
     Mul,
+    Div,
 }
 
-#[derive(Eq, PartialEq, Debug)]
-struct Column {
-    nums: Vec<String>,
-    op: Operation,
+impl Operator {
+    fn symbol(self) -> char {
+        match self {
+            Self::Add => '+',
+            Self::Sub => '-',
+            Self::Mul => '*',
+            Self::Div => '/',
+        }
+    }
+
+    fn apply(self, lhs: i128, rhs: i128) -> Result<i128, EvalError> {
+        let result = match self {
+            Self::Add => lhs.checked_add(rhs),
+            Self::Sub => lhs.checked_sub(rhs),
+            Self::Mul => lhs.checked_mul(rhs),
+            Self::Div if rhs == 0 => return Err(EvalError::DivisionByZero),
+            Self::Div => lhs.checked_div(rhs),
+        };
+        result.ok_or(EvalError::Overflow)
+    }
 }
 
-impl TryFrom<char> for Operation {
+impl TryFrom<char> for Operator {
     type Error = String;
 
     fn try_from(value: char) -> Result<Self, Self::Error> {
         match value {
             '+' => Ok(Self::Add),
+            '-' => Ok(Self::Sub),
             '*' => Ok(Self::Mul),
+            '/' => Ok(Self::Div),
             _ => Err("Unexpected operation".to_owned()),
         }
     }
 }
 
-pub fn part1(input: &str) -> u64 {
-    let cols = parse::parse_input(input);
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum EvalError {
+    Overflow,
+    DivisionByZero,
+}
 
-    cols.into_iter()
-        .map(|col| {
-            let nums = col
-                .nums
-                .into_iter()
-                .map(|num| num.trim().parse::<u64>().unwrap());
-            match col.op {
-                Operation::Add => nums.sum::<u64>(),
-                Operation::Mul => nums.product(),
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Overflow => f.write_str("result does not fit in 128 bits"),
+            Self::DivisionByZero => f.write_str("division by zero"),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Expr {
+    Num(i128),
+    /// The operator applied to the operands from left to right.
+    Apply(Operator, Vec<Expr>),
+}
+
+impl Expr {
+    pub fn eval(&self) -> Result<i128, EvalError> {
+        match self {
+            Self::Num(num) => Ok(*num),
+            Self::Apply(op, operands) => {
+                let mut operands = operands.iter().map(Self::eval);
+                let first = operands.next().unwrap_or(Ok(0))?;
+                operands.try_fold(first, |acc, operand| op.apply(acc, operand?))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Num(num) => write!(f, "{}", num),
+            Self::Apply(op, operands) => {
+                write!(f, "(")?;
+                for (i, operand) in operands.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " {} ", op.symbol())?;
+                    }
+                    write!(f, "{}", operand)?;
+                }
+                write!(f, ")")
             }
+        }
+    }
+}
+
+/// How the digits of a problem are read into numbers.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum Reading {
+    /// One number per row, from the top.
+    Rows,
+    /// One number per column, read top to bottom, from the rightmost column.
+    Columns,
+}
+
+/// A block of digits above its operator.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Problem {
+    /// The character column of the block's left edge.
+    pub column: usize,
+    /// One row of cells per line, `None` where the cell is blank.
+    pub cells: Vec<Vec<Option<u8>>>,
+    pub op: Operator,
+}
+
+impl Problem {
+    fn width(&self) -> usize {
+        self.cells[0].len()
+    }
+
+    /// The number of the digits, skipping blanks, or `None` without any.
+    fn number(digits: impl Iterator<Item = Option<u8>>) -> Result<Option<i128>, EvalError> {
+        digits.flatten().try_fold(None, |num: Option<i128>, digit| {
+            let num = num.unwrap_or(0).checked_mul(10);
+            let num = num.and_then(|num| num.checked_add(digit as i128));
+            num.map(Some).ok_or(EvalError::Overflow)
         })
-        .sum()
+    }
+
+    pub fn expr(&self, reading: Reading) -> Result<Expr, EvalError> {
+        let numbers: Vec<_> = match reading {
+            Reading::Rows => self
+                .cells
+                .iter()
+                .map(|row| Self::number(row.iter().copied()))
+                .collect::<Result<_, _>>()?,
+            Reading::Columns => (0..self.width())
+                .rev()
+                .map(|j| Self::number(self.cells.iter().map(|row| row[j])))
+                .collect::<Result<_, _>>()?,
+        };
+        let operands = numbers.into_iter().flatten().map(Expr::Num).collect();
+        Ok(Expr::Apply(self.op, operands))
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Worksheet {
+    pub problems: Vec<Problem>,
 }
 
-pub fn part2(input: &str) -> u64 {
-    let cols = parse::parse_input(input);
+impl Worksheet {
+    /// The sum of all problems.
+    pub fn total(&self, reading: Reading) -> Result<Expr, EvalError> {
+        let problems = self.problems.iter().map(|p| p.expr(reading));
+        Ok(Expr::Apply(
+            Operator::Add,
+            problems.collect::<Result<_, _>>()?,
+        ))
+    }
+}
 
-    cols.into_iter()
-        .map(|col| {
-            let term_count = col.nums[0].len();
-            let mut nums = vec![0; term_count];
-            for num in col.nums.iter() {
-                for (j, c) in num.chars().enumerate() {
-                    if c.is_numeric() {
-                        nums[j] = 10 * nums[j] + c.to_digit(10).unwrap() as u64;
+/// Renders the worksheet as it is written, with every line padded to the
+/// full width.
+impl fmt::Display for Worksheet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.problems.iter().map(|p| p.column + p.width()).max();
+        let mut lines = vec![vec![' '; width.unwrap_or(0)]; self.problems[0].cells.len() + 1];
+        for problem in &self.problems {
+            for (line, row) in lines.iter_mut().zip(&problem.cells) {
+                for (j, cell) in row.iter().enumerate() {
+                    if let Some(digit) = cell {
+                        line[problem.column + j] = char::from(b'0' + digit);
                     }
                 }
             }
-            match col.op {
-                Operation::Add => nums.iter().sum::<u64>(),
-                Operation::Mul => nums.iter().product(),
+            lines.last_mut().unwrap()[problem.column] = problem.op.symbol();
+        }
+        for line in lines {
+            writeln!(f, "{}", line.into_iter().collect::<String>())?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks that every number of the worksheet fits in an `i128` either way
+/// it is read, leaving the arithmetic to the parts.
+pub fn process(input: &str) -> Result<Input, ParseReport> {
+    let worksheet = parse::parse_input(input)?;
+    let rows = worksheet.problems[0].cells.len();
+    let operator_line: usize = input.split_inclusive('\n').take(rows).map(str::len).sum();
+    for reading in [Reading::Rows, Reading::Columns] {
+        for problem in &worksheet.problems {
+            if problem.expr(reading).is_err() {
+                let offset = operator_line + problem.column;
+                return Err(ParseReport::expected(
+                    input,
+                    offset,
+                    "numbers that fit in 128 bits",
+                ));
             }
-        })
-        .sum()
+        }
+    }
+    Ok(worksheet)
+}
+
+fn grand_total(input: &Input, reading: Reading) -> Result<i128, EvalError> {
+    input.total(reading)?.eval()
+}
+
+pub fn part1(input: &Input) -> Result<i128, EvalError> {
+    grand_total(input, Reading::Rows)
+}
+
+pub fn part2(input: &Input) -> Result<i128, EvalError> {
+    grand_total(input, Reading::Columns)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc_utils::aoc_test;
     use rstest::rstest;
 
-    #[rstest]
-    fn test_part1() {
-        let input = "123 328  51 64 
+    const SAMPLE_INPUT: &str = "123 328  51 64 
  45 64  387 23 
   6 98  215 314
 *   +   *   +  
 
 ";
-        let actual = part1(input);
-        let expected = 4277556;
-        assert_eq!(actual, expected);
+
+    aoc_test!(
+        day06,
+        example = SAMPLE_INPUT,
+        part1 = Ok(4277556),
+        part2 = Ok(3263827)
+    );
+
+    #[rstest]
+    fn test_parse_error() {
+        let report = process("1 2\n3 4\n+ %\n").unwrap_err();
+        assert_eq!((report.line, report.column), (3, 3));
+
+        let report = process("1 2\n3 4\n+\n").unwrap_err();
+        assert_eq!((report.line, report.column), (3, 2));
+        assert_eq!(report.expected, vec!["one operator per problem"]);
     }
 
     #[rstest]
-    fn test_part2() {
-        let input = "123 328  51 64 
- 45 64  387 23 
-  6 98  215 314
-*   +   *   +  
+    fn test_expressions() {
+        let input = process(SAMPLE_INPUT).unwrap();
+        let expr = input.problems[3].expr(Reading::Columns).unwrap();
+        assert_eq!(expr.to_string(), "(4 + 431 + 623)");
+        let expr = input.problems[2].expr(Reading::Rows).unwrap();
+        assert_eq!(expr.to_string(), "(51 * 387 * 215)");
 
-";
-        let actual = part2(input);
-        let expected = 3263827;
-        assert_eq!(actual, expected);
+        let input = process("20 9\n 3 3\n-  /\n").unwrap();
+        assert_eq!(part1(&input), Ok(17 + 3));
+        assert_eq!(part2(&input), Ok(3 - 2 + 93));
+    }
+
+    #[rstest]
+    fn test_eval_errors() {
+        let input = process("1 2\n0 0\n+ /\n").unwrap();
+        assert_eq!(part1(&input), Err(EvalError::DivisionByZero));
+        assert_eq!(part2(&input), Ok(10 + 20));
+
+        let huge = "9".repeat(39);
+        let report = process(&format!("{}\n*\n", huge)).unwrap_err();
+        assert_eq!((report.line, report.column), (2, 1));
+        assert_eq!(report.expected, vec!["numbers that fit in 128 bits"]);
+
+        let big = "9".repeat(20);
+        let input = process(&format!("{} 1\n{} 1\n*  +\n", big, big)).unwrap();
+        assert_eq!(part1(&input), Err(EvalError::Overflow));
+
+        let max = i128::MAX.to_string();
+        let input = process(&format!("{} 1\n+{} +\n", max, " ".repeat(max.len() - 1))).unwrap();
+        assert_eq!(part1(&input), Err(EvalError::Overflow));
+    }
+
+    #[rstest]
+    fn test_render() {
+        let input = process(SAMPLE_INPUT).unwrap();
+        assert_eq!(
+            input.to_string(),
+            SAMPLE_INPUT.trim_end_matches('\n').to_owned() + "\n"
+        );
     }
 }
 
 mod parse {
 
-    use super::{Column, Operation};
+    use super::{Operator, Problem, Worksheet};
+    use aoc_utils::parse::{ParseReport, expected, label, parse_all};
     use winnow::{
-        Parser, Result,
+        ModalResult, Parser,
         ascii::{newline, space1},
         combinator::separated,
-        token::{one_of, rest, take_while},
+        token::{one_of, take_while},
     };
 
-    fn input_parser(input: &mut &str) -> Result<Vec<Column>> {
-        let (number_lines, _, operations): (Vec<&str>, (), Vec<Operation>) = (
+    fn input_parser<'i>(input: &mut &'i str) -> ModalResult<(Vec<&'i str>, Vec<Operator>)> {
+        let (number_lines, _, operations) = (
             separated(
                 1..,
-                take_while(1.., |c: char| c.is_numeric() || c == ' '),
+                take_while(1.., |c: char| c.is_ascii_digit() || c == ' '),
                 newline,
-            ),
+            )
+            .context(label("numbers")),
             newline.void(),
             separated(
                 1..,
-                one_of(['+', '*']).map(|c: char| c.try_into().unwrap()),
+                one_of(['+', '-', '*', '/'])
+                    .map(|c: char| Operator::try_from(c).unwrap())
+                    .context(expected("operation")),
                 space1,
-            ),
+            )
+            .context(label("operations")),
         )
             .parse_next(input)?;
+        Ok((number_lines, operations))
+    }
 
-        let _ = rest.parse_next(input)?;
-
-        let num_chars = number_lines[0].len();
-
-        let is_separator = number_lines
+    /// Splits the lines of digits into problems at the columns that are
+    /// blank on every line, which may have different lengths.
+    fn problems(number_lines: &[&str], operations: Vec<Operator>) -> Option<Vec<Problem>> {
+        let width = number_lines
             .iter()
-            .fold(vec![true; num_chars], |mut acc, line| {
-                for (v, c) in acc.iter_mut().zip(line.chars()) {
-                    *v = *v && c == ' ';
-                }
-                acc
-            });
-        let mut col_nums = vec![vec![]; operations.len()];
-        for line in number_lines {
-            let mut num = String::new();
-            let mut col_nums_iter = col_nums.iter_mut();
-            for (char, is_sep) in line.chars().zip(is_separator.iter()) {
-                if !is_sep {
-                    num.push(char);
-                } else {
-                    if let Some(l) = col_nums_iter.next() {
-                        l.push(num.clone())
-                    }
-                    num.clear();
-                }
+            .map(|line| line.len())
+            .max()
+            .unwrap_or(0);
+        let cell = |line: &str, j: usize| {
+            let byte = line.as_bytes().get(j).copied().unwrap_or(b' ');
+            byte.is_ascii_digit().then(|| byte - b'0')
+        };
+        let blank = |j: usize| number_lines.iter().all(|line| cell(line, j).is_none());
+
+        let mut operations = operations.into_iter();
+        let mut problems = vec![];
+        let mut j = 0;
+        while j < width {
+            if blank(j) {
+                j += 1;
+                continue;
             }
-            if let Some(l) = col_nums_iter.next() {
-                l.push(num);
+            let column = j;
+            while j < width && !blank(j) {
+                j += 1;
             }
+            let cells = number_lines
+                .iter()
+                .map(|line| (column..j).map(|j| cell(line, j)).collect())
+                .collect();
+            problems.push(Problem {
+                column,
+                cells,
+                op: operations.next()?,
+            });
         }
-
-        Ok(col_nums
-            .into_iter()
-            .zip(operations)
-            .map(|(nums, op)| Column { nums, op })
-            .collect())
+        (operations.next().is_none() && !problems.is_empty()).then_some(problems)
     }
 
-    pub(crate) fn parse_input(input: &str) -> Vec<Column> {
-        input_parser.parse(input).expect("invalid input")
+    pub(crate) fn parse_input(input: &str) -> Result<Worksheet, ParseReport> {
+        let (number_lines, operations) = parse_all(input_parser, input)?;
+        match problems(&number_lines, operations) {
+            Some(problems) => Ok(Worksheet { problems }),
+            None => {
+                let end = input.trim_end().len();
+                Err(ParseReport::expected(
+                    input,
+                    end,
+                    "one operator per problem",
+                ))
+            }
+        }
     }
 
     #[cfg(test)]
@@ -168,30 +392,43 @@ mod parse {
 
         #[rstest]
         fn test_parse() {
-            let input = "123 328  51 64 
- 45 64  387 23 
+            let input = "123 328  51 64
+ 45 64  387 23
   6 98  215 314
 *   +   *   +  ";
-            let actual = parse_input(input);
+            let actual = parse_input(input).unwrap();
+            let digits = |rows: &[&str]| {
+                rows.iter()
+                    .map(|row| {
+                        row.bytes()
+                            .map(|b| b.is_ascii_digit().then(|| b - b'0'))
+                            .collect()
+                    })
+                    .collect()
+            };
             let expected = vec![
-                Column {
-                    nums: vec!["123".into(), " 45".into(), "  6".into()],
-                    op: Operation::Mul,
+                Problem {
+                    column: 0,
+                    cells: digits(&["123", " 45", "  6"]),
+                    op: Operator::Mul,
                 },
-                Column {
-                    nums: vec!["328".into(), "64 ".into(), "98 ".into()],
-                    op: Operation::Add,
+                Problem {
+                    column: 4,
+                    cells: digits(&["328", "64 ", "98 "]),
+                    op: Operator::Add,
                 },
-                Column {
-                    nums: vec![" 51".into(), "387".into(), "215".into()],
-                    op: Operation::Mul,
+                Problem {
+                    column: 8,
+                    cells: digits(&[" 51", "387", "215"]),
+                    op: Operator::Mul,
                 },
-                Column {
-                    nums: vec!["64 ".into(), "23 ".into(), "314".into()],
-                    op: Operation::Add,
+                Problem {
+                    column: 12,
+                    cells: digits(&["64 ", "23 ", "314"]),
+                    op: Operator::Add,
                 },
             ];
-            assert_eq!(expected, actual);
+            assert_eq!(Worksheet { problems: expected }, actual);
         }
     }
 }
@@ -1,4 +1,9 @@
-use itertools::Itertools;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use aoc_utils::bigint::BigUint;
+use aoc_utils::parse::ParseReport;
+use aoc_utils::visualize::{Frame, Recorder, Rgb};
 
 #[derive(Clone, Debug, PartialEq)]
 enum Cell {
@@ -7,11 +12,13 @@ enum Cell {
     Splitter,
 }
 
-struct Grid {
+#[derive(Debug)]
+pub struct Grid {
     items: Vec<Vec<Cell>>,
 }
 
-type Pos = (usize, usize);
+/// Row and column.
+pub type Pos = (usize, usize);
 
 impl Grid {
     fn start(&self) -> Pos {
@@ -26,86 +33,202 @@ impl Grid {
     fn size(&self) -> (usize, usize) {
         (self.items.len(), self.items[0].len())
     }
+
+    /// Where a beam entering column `c` at row `r` ends, falling down until it
+    /// hits a splitter or leaves the bottom.
+    fn target(&self, (r, c): Pos) -> Target {
+        (r..self.items.len())
+            .find(|&r| self.items[r][c] == Cell::Splitter)
+            .map_or(Target::Exit(c), |r| Target::Splitter((r, c)))
+    }
 }
 
-fn init_ray_counts(start: usize, width: usize) -> Vec<u64> {
-    let mut ray_counts = vec![0u64; width];
-    ray_counts[start] = 1;
-    ray_counts
+/// Where a beam ends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Target {
+    Splitter(Pos),
+    /// Leaves the bottom of the manifold in this column.
+    Exit(usize),
 }
 
-pub fn part1(input: &str) -> u32 {
-    let grid = parse::parse_input(input);
-    let (_, w) = grid.size();
+/// The splitters reached from the start, each with the targets of the beams
+/// it sends left and right.
+///
+/// A split beam starts in the cell beside the splitter, so it is lost when
+/// that cell is outside the manifold or another splitter.
+#[derive(Clone, Debug)]
+pub struct BeamDag {
+    height: usize,
+    width: usize,
+    start: Pos,
+    /// The target of the beam from the start.
+    first: Target,
+    /// Keyed by position, so that every splitter comes after the splitters
+    /// feeding it, which are in earlier rows.
+    splitters: BTreeMap<Pos, [Option<Target>; 2]>,
+}
+
+impl BeamDag {
+    pub fn new(grid: &Grid) -> Self {
+        let (height, width) = grid.size();
+        let (r, c) = grid.start();
+        let first = grid.target((r + 1, c));
+
+        let mut splitters = BTreeMap::new();
+        let mut stack = vec![first];
+        while let Some(target) = stack.pop() {
+            let Target::Splitter((r, c)) = target else {
+                continue;
+            };
+            if splitters.contains_key(&(r, c)) {
+                continue;
+            }
+            let outputs = [c.checked_sub(1), c.checked_add(1)].map(|column| {
+                let column = column.filter(|&column| column < width)?;
+                (grid.items[r][column] == Cell::None).then(|| grid.target((r + 1, column)))
+            });
+            stack.extend(outputs.iter().flatten());
+            splitters.insert((r, c), outputs);
+        }
 
-    let start = grid.start().1;
+        Self {
+            height,
+            width,
+            start: (r, c),
+            first,
+            splitters,
+        }
+    }
 
-    let ray_counts = init_ray_counts(start, w);
+    /// Every splitter a beam reaches, in row-major order.
+    pub fn reachable_splitters(&self) -> impl Iterator<Item = Pos> + '_ {
+        self.splitters.keys().copied()
+    }
 
-    let mut split_count = 0;
-    grid.items[1..]
-        .iter()
-        .fold(ray_counts, |mut ray_counts, row| {
-            for idx in row.iter().positions(|c| matches!(c, Cell::Splitter)) {
-                if ray_counts[idx] > 0 {
-                    let tmp_count = std::mem::replace(&mut ray_counts[idx], 0);
-                    for offset in [-1, 1] {
-                        if let Some(column) = idx.checked_add_signed(offset as isize)
-                            && column < w
-                            && row[column] == Cell::None
-                        {
-                            ray_counts[column] += tmp_count;
-                        }
-                    }
-                    split_count += 1;
+    /// The beams in the order they are split: the splitter sending each, or
+    /// `None` for the start, the cell where it starts, the number of
+    /// timelines following it and where it ends.
+    fn beams(&self) -> Vec<(Option<Pos>, Pos, BigUint, Target)> {
+        let mut arriving: BTreeMap<Target, BigUint> = BTreeMap::new();
+        let one = BigUint::from(1u64);
+        arriving.insert(self.first, one.clone());
+        let mut beams = vec![(None, self.start, one, self.first)];
+        for (&(r, c), outputs) in &self.splitters {
+            let count = arriving
+                .get(&Target::Splitter((r, c)))
+                .cloned()
+                .unwrap_or_default();
+            for (column, target) in [c.wrapping_sub(1), c + 1].into_iter().zip(outputs) {
+                if let Some(target) = target {
+                    *arriving.entry(*target).or_default() += &count;
+                    beams.push((Some((r, c)), (r, column), count.clone(), *target));
                 }
             }
-            ray_counts
-        })
-        .len();
-    split_count
+        }
+        beams
+    }
+
+    /// How many timelines pass through each cell, indexed by row then column.
+    pub fn path_counts(&self) -> Vec<Vec<BigUint>> {
+        let mut counts = vec![vec![BigUint::zero(); self.width]; self.height];
+        for (_, (r, c), count, target) in self.beams() {
+            let end = match target {
+                Target::Splitter((end, _)) => end,
+                Target::Exit(_) => self.height - 1,
+            };
+            for row in &mut counts[r..=end] {
+                row[c] += &count;
+            }
+        }
+        counts
+    }
+
+    /// The number of timelines, one for every way out of the bottom.
+    pub fn timelines(&self) -> BigUint {
+        self.beams()
+            .into_iter()
+            .filter(|(_, _, _, target)| matches!(target, Target::Exit(_)))
+            .map(|(_, _, count, _)| count)
+            .sum()
+    }
+
+    /// The beams in Graphviz DOT, labelled with their number of timelines.
+    pub fn to_dot(&self) -> String {
+        let name = |target: Target| match target {
+            Target::Splitter((r, c)) => format!("\"{},{}\"", r, c),
+            Target::Exit(c) => format!("\"exit {}\"", c),
+        };
+        let mut dot = String::from("digraph beams {\n");
+        writeln!(dot, "    \"S\" [shape=box];").unwrap();
+        for (source, _, count, target) in self.beams() {
+            let from = source.map_or("\"S\"".to_owned(), |pos| name(Target::Splitter(pos)));
+            writeln!(
+                dot,
+                "    {} -> {} [label=\"{}\"];",
+                from,
+                name(target),
+                count
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
 }
 
-pub fn part2(input: &str) -> u64 {
-    let grid = parse::parse_input(input);
-    let (_, w) = grid.size();
+pub fn process(input: &str) -> Result<Grid, ParseReport> {
+    parse::parse_input(input)
+}
 
-    let start = grid.start();
+pub fn part1(grid: &Grid) -> u32 {
+    BeamDag::new(grid).reachable_splitters().count() as u32
+}
 
-    let ray_counts = init_ray_counts(start.1, w);
+pub fn part2(grid: &Grid) -> BigUint {
+    BeamDag::new(grid).timelines()
+}
 
-    grid.items[1..]
+/// Draws the manifold one row at a time as the beams reach it. Beams get
+/// brighter with the number of timelines through them, and splitters turn from
+/// grey to yellow once a beam hits them.
+pub fn visualize(grid: &Grid, recorder: &mut Recorder) -> std::io::Result<()> {
+    let dag = BeamDag::new(grid);
+    let counts = dag.path_counts();
+    let most_bits = counts
         .iter()
-        .fold(ray_counts, |mut ray_counts, row| {
-            for (idx, count) in ray_counts
-                .clone()
-                .into_iter()
-                .enumerate()
-                .filter(|(_, c)| *c > 0)
-            {
-                if row[idx] == Cell::Splitter {
-                    for offset in [-1, 1] {
-                        if let Some(column) = idx.checked_add_signed(offset as isize)
-                            && column < w
-                            && row[column] == Cell::None
-                        {
-                            ray_counts[column] += count;
-                        }
-                    }
-                    ray_counts[idx] = 0;
-                }
-            }
+        .flatten()
+        .map(BigUint::bits)
+        .max()
+        .unwrap_or(0);
 
-            ray_counts
-        })
-        .iter()
-        .sum::<u64>()
+    let mut frame = Frame::from_grid(&grid.items, |cell| match cell {
+        Cell::None => Rgb::BLACK,
+        Cell::Start => Rgb::GREEN,
+        Cell::Splitter => Rgb::GREY,
+    });
+    let beam = |count: &BigUint| {
+        let brightness = count.bits() as f64 / most_bits.max(1) as f64;
+        let channel = |low: f64| (low + (255.0 - low) * brightness) as u8;
+        Rgb(channel(20.0), channel(80.0), channel(200.0))
+    };
+    for (r, row) in counts.iter().enumerate().skip(1) {
+        for (c, count) in row.iter().enumerate().filter(|(_, n)| !n.is_zero()) {
+            let color = match grid.items[r][c] {
+                Cell::Splitter => Rgb::YELLOW,
+                _ => beam(count),
+            };
+            frame.set(c, r, color);
+        }
+        recorder.record(&frame)?;
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rstest::rstest;
+    use aoc_utils::aoc_test;
+
     const SAMPLE_INPUT: &str = ".......S.......
 ...............
 .......^.......
@@ -124,53 +247,91 @@ mod tests {
 ...............
 ";
 
-    #[rstest]
-    fn test_part1() {
-        let actual = part1(SAMPLE_INPUT);
-        let expected = 21;
-        assert_eq!(actual, expected);
+    aoc_test!(
+        day07,
+        example = SAMPLE_INPUT,
+        part1 = 21,
+        part2 = BigUint::from(40u64)
+    );
+
+    #[test]
+    fn test_beam_dag() {
+        let grid = process(".S.\n.^.\n^..\n...\n").unwrap();
+        let dag = BeamDag::new(&grid);
+        assert_eq!(
+            dag.reachable_splitters().collect::<Vec<_>>(),
+            vec![(1, 1), (2, 0)]
+        );
+        assert_eq!(dag.timelines(), BigUint::from(2u64));
+        let counts: Vec<Vec<_>> = dag
+            .path_counts()
+            .iter()
+            .map(|row| row.iter().map(|n| n.to_u128().unwrap()).collect())
+            .collect();
+        assert_eq!(
+            counts,
+            vec![vec![0, 1, 0], vec![1, 1, 1], vec![1, 1, 1], vec![0, 1, 1]]
+        );
+        assert_eq!(
+            dag.to_dot(),
+            "digraph beams {
+    \"S\" [shape=box];
+    \"S\" -> \"1,1\" [label=\"1\"];
+    \"1,1\" -> \"2,0\" [label=\"1\"];
+    \"1,1\" -> \"exit 2\" [label=\"1\"];
+    \"2,0\" -> \"exit 1\" [label=\"1\"];
+}
+"
+        );
     }
 
-    #[rstest]
-    fn test_part2() {
-        let actual = part2(SAMPLE_INPUT);
-        let expected = 40;
-        assert_eq!(actual, expected);
+    #[test]
+    fn test_deep_grid() {
+        // Every splitter doubles the timelines, and beams from neighbouring
+        // splitters merge, giving a row of binomial coefficients.
+        let depth: usize = 150;
+        let width = 2 * depth + 3;
+        let mut input = String::new();
+        for r in 0..2 * depth + 1 {
+            let row: String = (0..width)
+                .map(|c| match (r, c.abs_diff(depth + 1)) {
+                    (0, 0) => 'S',
+                    (r, d) if r % 2 == 1 && d <= r / 2 && (r / 2 - d) % 2 == 0 => '^',
+                    _ => '.',
+                })
+                .collect();
+            input.push_str(&row);
+            input.push('\n');
+        }
+        let grid = process(&input).unwrap();
+        let timelines = part2(&grid);
+        assert_eq!(timelines.bits(), depth as u64 + 1);
+        assert_eq!(timelines.to_u128(), None);
     }
 }
 
 mod parse {
     use super::{Cell, Grid};
-    use winnow::ascii::newline;
-    use winnow::combinator::{alt, repeat, separated};
-    use winnow::token::rest;
-    use winnow::{Parser, Result};
+    use aoc_utils::parse::{ParseReport, expected, label, parse_all, rect_grid};
+    use winnow::combinator::alt;
+    use winnow::{ModalResult, Parser};
 
-    fn cell(input: &mut &str) -> Result<Cell> {
+    fn cell(input: &mut &str) -> ModalResult<Cell> {
         alt((
             '.'.value(Cell::None),
             'S'.value(Cell::Start),
             '^'.value(Cell::Splitter),
         ))
+        .context(expected("`.`, `S` or `^`"))
         .parse_next(input)
     }
 
-    fn grid_parser(input: &mut &str) -> Result<Vec<Vec<Cell>>> {
-        let grid: Vec<Vec<Cell>> = separated(
-            1..,
-            repeat::<&str, Cell, Vec<Cell>, _, _>(1.., cell),
-            newline,
-        )
-        .parse_next(input)?;
-
-        let _ = rest.parse_next(input)?;
-
-        Ok(grid)
-    }
-
-    pub(crate) fn parse_input(input: &str) -> Grid {
-        let items = grid_parser.parse(input).expect("could not read input");
-        Grid { items }
+    pub(crate) fn parse_input(input: &str) -> Result<Grid, ParseReport> {
+        let items = parse_all(rect_grid(cell).context(label("manifold")), input)?;
+        if !items[0].contains(&Cell::Start) {
+            return Err(ParseReport::expected(input, 0, "`S` in the first row"));
+        }
+        Ok(Grid { items })
     }
 
     #[cfg(test)]
@@ -199,10 +360,22 @@ mod parse {
 
         #[rstest]
         fn test_parse_input() {
-            let grid = parse_input(SAMPLE_INPUT);
+            let grid = parse_input(SAMPLE_INPUT).unwrap();
 
             let start = grid.start();
             assert_eq!(start, (0, 7));
         }
+
+        #[rstest]
+        fn test_parse_error() {
+            let report = parse_input("..S..\n..|..\n").unwrap_err();
+            assert_eq!((report.line, report.column), (2, 3));
+            assert_eq!(report.expected, vec!["`.`, `S` or `^`"]);
+
+            let report = parse_input("..S..\n..^.\n").unwrap_err();
+            assert_eq!((report.line, report.column), (2, 1));
+            let report = parse_input(".....\n..^..\n").unwrap_err();
+            assert_eq!(report.expected, vec!["`S` in the first row"]);
+        }
     }
 }
@@ -1,125 +1,69 @@
+use aoc_utils::kdtree::KdTree;
+use aoc_utils::mst::{DisjointSet, kruskal};
+use aoc_utils::parse::ParseReport;
 use itertools::Itertools;
 
-type Input = (u32, u32);
+type Input = (u32, u64);
 
-#[cfg(test)]
-const LIMIT: usize = 10;
-
-#[cfg(not(test))]
-const LIMIT: usize = 1000;
-
-#[derive(Eq, PartialEq, Debug, Copy, Clone)]
-struct Junction(u32, u32, u32);
-
-#[derive(Debug)]
-struct Edge<'j>(&'j Junction, &'j Junction);
-
-impl Junction {
-    #[inline]
-    fn distance(&self, other: &Self) -> u64 {
-        (self.0 as u64).abs_diff(other.0 as u64).pow(2)
-            + (self.1 as u64).abs_diff(other.1 as u64).pow(2)
-            + (self.2 as u64).abs_diff(other.2 as u64).pow(2)
+aoc_utils::params! {
+    pub struct Params {
+        /// How many of the closest pairs are connected for part 1.
+        pub limit: usize = 1000,
     }
 }
 
-impl Edge<'_> {
-    #[inline]
-    fn distance(&self) -> u64 {
-        self.0.distance(self.1)
-    }
-}
-
-type Circuit = Vec<Junction>;
-
-struct Circuits {
-    circuits: Vec<Circuit>,
-}
-
-impl Circuits {
-    fn new(junctions: &[Junction]) -> Self {
-        let circuits = junctions.iter().map(|j| vec![*j]).collect();
-        Self { circuits }
-    }
-
-    fn add_connection(&mut self, edge: &Edge) {
-        let circuits = self.circuits.drain(..);
-        let (to_merge, rest): (Vec<_>, Vec<_>) =
-            circuits.partition(|c| c.contains(edge.0) || c.contains(edge.1));
-        assert!(to_merge.len() <= 2);
-
-        let merged = to_merge.into_iter().reduce(|mut acc, c| {
-            acc.extend(c);
-            acc
-        });
-        self.circuits.extend(rest.into_iter().chain(merged));
-    }
-
-    fn iter(&self) -> impl Iterator<Item = &Vec<Junction>> {
-        self.circuits.iter()
-    }
-
-    #[inline]
-    fn len(&self) -> usize {
-        self.circuits.len()
-    }
-}
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+struct Junction(u32, u32, u32);
 
-fn edges_sorted(junctions: &[Junction]) -> impl Iterator<Item = Edge<'_>> {
-    let len = junctions.len();
-    let num_connections = len * (len - 1) / 2;
-    let mut edges = Vec::with_capacity(num_connections);
-    for (i, j1) in junctions.iter().enumerate().skip(1) {
-        for j2 in junctions.iter().take(i) {
-            edges.push(Edge(j1, j2));
-        }
+pub fn process(input: &str, params: &Params) -> Result<Input, ParseReport> {
+    let junctions = parse::parse_input(input)?;
+    if junctions.len() < 2 {
+        let end = input.trim_end().len();
+        return Err(ParseReport::expected(
+            input,
+            end,
+            "at least two junction boxes",
+        ));
     }
-    assert_eq!(edges.len(), num_connections);
-    edges
-        .into_iter()
-        .sorted_unstable_by(|e1, e2| e1.distance().partial_cmp(&e2.distance()).unwrap())
-}
-
-pub fn process(input: &str) -> Input {
-    let junctions = parse::parse_input(input);
 
-    let sorted = edges_sorted(&junctions);
+    let points = junctions
+        .iter()
+        .map(|j| [j.0 as i64, j.1 as i64, j.2 as i64])
+        .collect();
+    let tree = KdTree::new(points);
+    let pairs = || tree.pairs().map(|(distance, a, b)| (a, b, distance));
 
-    let mut connections = sorted;
-    let mut circuits = Circuits::new(&junctions);
-    for edge in connections.by_ref().take(LIMIT) {
-        circuits.add_connection(&edge);
+    // Only the circuits matter for part 1, not which pairs were connected.
+    let mut circuits = DisjointSet::new(junctions.len());
+    for (a, b, _) in pairs().take(params.limit) {
+        circuits.union(a, b);
     }
     let part1 = circuits
-        .iter()
-        .map(|c| c.len() as u32)
+        .sizes()
+        .map(|size| size as u32)
         .sorted_unstable()
         .rev()
         .take(3)
         .product();
 
-    for edge in connections {
-        circuits.add_connection(&edge);
-        if circuits.len() == 1 {
-            let part2 = edge.0.0 * edge.1.0;
-            return (part1, part2);
-        }
-    }
-    unreachable!()
+    let forest = kruskal(junctions.len(), pairs());
+    let last = forest.edges.last().expect("at least two junctions");
+    let part2 = junctions[last.a].0 as u64 * junctions[last.b].0 as u64;
+    Ok((part1, part2))
 }
 
 pub fn part1(input: &Input) -> u32 {
     input.0
 }
 
-pub fn part2(input: &Input) -> u32 {
+pub fn part2(input: &Input) -> u64 {
     input.1
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rstest::rstest;
+    use aoc_utils::aoc_test;
 
     const SAMPLE_INPUT: &str = "162,817,812
 57,618,57
@@ -143,50 +87,48 @@ mod tests {
 425,690,689
 ";
 
-    #[rstest]
-    fn test_process() {
-        process(SAMPLE_INPUT);
-    }
+    aoc_test!(
+        day08,
+        example = SAMPLE_INPUT,
+        part1 = 40,
+        part2 = 25272,
+        params = { limit: 10 },
+    );
 
-    #[rstest]
-    fn test_part1() {
-        let actual = part1(&process(SAMPLE_INPUT));
-        let expected = 40;
-        assert_eq!(expected, actual);
+    #[test]
+    fn test_few_junctions() {
+        let input = "4294967295,0,4294967295\n0,4294967295,0\n1,2,3\n";
+        assert_eq!(process(input, &Params::default()), Ok((3, 4294967295)));
+
+        assert!(process("1,2,3\n1,2,3\n1,2,3\n", &Params::default()).is_ok());
+
+        let report = process("1,2,3\n", &Params::default()).unwrap_err();
+        assert_eq!(report.expected, vec!["at least two junction boxes"]);
     }
 
-    #[rstest]
-    fn test_part2() {
-        let actual = part2(&process(SAMPLE_INPUT));
-        let expected = 25272;
-        assert_eq!(expected, actual);
+    #[test]
+    fn test_many_junctions() {
+        // A line of junctions one apart, then one more a bit further out that
+        // is only connected after every pair up to that distance.
+        let mut input: String = (0..2000).map(|x| format!("{},0,0\n", x)).collect();
+        input.push_str("2010,0,0\n");
+        let params = Params { limit: 1998 };
+        assert_eq!(process(&input, &params), Ok((1999, 1999 * 2010)));
     }
 }
 
 mod parse {
-
-    use winnow::{
-        ModalResult, Parser,
-        ascii::{digit1, newline},
-        combinator::separated,
-        token::rest,
-    };
+    use aoc_utils::ints::lines_of;
+    use aoc_utils::parse::ParseReport;
 
     use crate::day08::Junction;
 
-    fn junction(input: &mut &str) -> ModalResult<Junction> {
-        let numbers: Vec<_> = separated(3, digit1.parse_to::<u32>(), ',').parse_next(input)?;
-        Ok(Junction(numbers[0], numbers[1], numbers[2]))
-    }
-
-    fn parser(input: &mut &str) -> ModalResult<Vec<Junction>> {
-        let junctions: Vec<_> = separated(1.., junction, newline).parse_next(input)?;
-        rest.parse_next(input)?;
-        Ok(junctions)
-    }
-
-    pub fn parse_input(input: &str) -> Vec<Junction> {
-        parser.parse(input).unwrap()
+    pub fn parse_input(input: &str) -> Result<Vec<Junction>, ParseReport> {
+        let junctions = lines_of::<[u32; 3]>(input)?;
+        Ok(junctions
+            .into_iter()
+            .map(|[x, y, z]| Junction(x, y, z))
+            .collect())
     }
 
     #[cfg(test)]
@@ -201,7 +143,7 @@ mod parse {
 906,360,560
 
 ";
-            let actual = parse_input(input);
+            let actual = parse_input(input).unwrap();
             let expected = vec![
                 Junction(162, 817, 812),
                 Junction(57, 618, 57),
@@ -209,5 +151,12 @@ mod parse {
             ];
             assert_eq!(actual, expected);
         }
+
+        #[rstest]
+        fn test_parse_error() {
+            let report = parse_input("1,2,3\n4,5\n").unwrap_err();
+            assert_eq!((report.line, report.column), (2, 4));
+            assert_eq!(report.expected, vec!["3 integers, found 2"]);
+        }
     }
 }
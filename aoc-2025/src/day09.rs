@@ -1,78 +1,134 @@
-use std::collections::VecDeque;
+use std::collections::{BTreeSet, VecDeque};
+use std::str::FromStr;
 
+use aoc_utils::parse::ParseReport;
+use aoc_utils::visualize::{Frame, Recorder, Rgb};
 use itertools::Itertools;
 
-type Tile = (u64, u64);
+pub type Tile = (u64, u64);
 
-type Input = Vec<Tile>;
+aoc_utils::params! {
+    pub struct Params {
+        /// How part 2 checks that a rectangle stays within the loop.
+        pub strategy: Strategy = Strategy::Grid,
+    }
+}
 
-pub fn process(input: &str) -> Input {
-    parse::parse_input(input)
+/// Ways of checking rectangles against the loop of red and green tiles.
+///
+/// Both treat the loop as a region of the plane, so a rectangle may not cross
+/// a gap in it even when the gap is too narrow to hold a tile.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// Prefix sums over a grid compressed to the coordinates of the corners,
+    /// with `O(n^2)` cells.
+    Grid,
+    /// Checks the sides of each rectangle against the parts of every line
+    /// through a corner that lie within the loop, found by a sweep line.
+    Sweep,
 }
 
-pub fn part1(input: &Input) -> u64 {
-    let mut max_area = 0;
-    for (i, (x1, y1)) in input.iter().enumerate() {
-        for (x2, y2) in input.iter().skip(i + 1) {
-            let dx = x1.abs_diff(*x2) + 1;
-            let dy = y1.abs_diff(*y2) + 1;
-            max_area = max_area.max(dx * dy);
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "grid" => Ok(Self::Grid),
+            "sweep" => Ok(Self::Sweep),
+            _ => Err("expected grid or sweep".to_owned()),
         }
     }
-    max_area
 }
 
-struct CompressedTile((usize, usize), (u64, u64));
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Floor {
+    /// The red tiles, in order around the loop.
+    pub tiles: Vec<Tile>,
+    pub strategy: Strategy,
+}
+
+type Input = Floor;
+
+pub fn process(input: &str, params: &Params) -> Result<Input, ParseReport> {
+    Ok(Floor {
+        tiles: parse::parse_input(input)?,
+        strategy: params.strategy,
+    })
+}
 
-impl CompressedTile {
-    fn compressed(&self) -> &(usize, usize) {
-        &self.0
+/// Area of the rectangle with opposite corners `a` and `b`, counting tiles.
+fn area(a: &Tile, b: &Tile) -> u128 {
+    (u128::from(a.0.abs_diff(b.0)) + 1) * (u128::from(a.1.abs_diff(b.1)) + 1)
+}
+
+/// Opposite corners of the largest rectangle between two red tiles.
+fn largest_rectangle(tiles: &[Tile]) -> Option<(Tile, Tile)> {
+    largest_fitting(tiles, |_, _| true)
+}
+
+pub fn part1(input: &Input) -> u128 {
+    largest_rectangle(&input.tiles).map_or(0, |(a, b)| area(&a, &b))
+}
+
+/// Opposite corners of the first of the largest rectangles between red tiles
+/// `i` and `j` for which `fits(i, j)`, only called for rectangles larger
+/// than the best so far.
+fn largest_fitting(
+    tiles: &[Tile],
+    mut fits: impl FnMut(usize, usize) -> bool,
+) -> Option<(Tile, Tile)> {
+    let mut largest: Option<(Tile, Tile)> = None;
+    for (i, a) in tiles.iter().enumerate() {
+        for (j, b) in tiles.iter().enumerate().skip(i + 1) {
+            if largest.is_none_or(|(c, d)| area(a, b) > area(&c, &d)) && fits(i, j) {
+                largest = Some((*a, *b));
+            }
+        }
     }
+    largest
+}
 
-    fn tile(&self) -> &Tile {
-        &self.1
+/// Opposite corners of the largest rectangle between two red tiles that only
+/// covers red or green tiles.
+pub fn largest_inside_rectangle(tiles: &[Tile], strategy: Strategy) -> Option<(Tile, Tile)> {
+    match strategy {
+        Strategy::Grid => largest_inside_grid(tiles),
+        Strategy::Sweep => largest_inside_sweep(tiles),
     }
 }
 
-const OUTSIDE: i32 = 0;
-const INSIDE: i32 = 1;
-const UNKNOWN: i32 = 2;
+const OUTSIDE: u8 = 0;
+const INSIDE: u8 = 1;
+const UNKNOWN: u8 = 2;
 
-pub fn part2(input: &Input) -> u64 {
-    let num_corners = input.len();
+/// Index of coordinate `c` in a compressed grid over the sorted distinct
+/// `coordinates`, where the odd indices are the coordinates and the even
+/// ones the gaps around them.
+fn compress(coordinates: &[u64], c: u64) -> usize {
+    2 * coordinates.binary_search(&c).expect("a corner coordinate") + 1
+}
 
-    let x_sorted = input
+fn largest_inside_grid(tiles: &[Tile]) -> Option<(Tile, Tile)> {
+    let xs = tiles
         .iter()
-        .map(|c| c.0)
-        .chain([0, u64::MAX])
+        .map(|t| t.0)
         .sorted_unstable()
-        .dedup();
-    let y_sorted = input
+        .dedup()
+        .collect_vec();
+    let ys = tiles
         .iter()
-        .map(|c| c.1)
-        .chain([0, u64::MAX])
+        .map(|t| t.1)
         .sorted_unstable()
-        .dedup();
-
-    let x_compressed = x_sorted.collect_vec();
-    let y_compressed = y_sorted.collect_vec();
-
-    let compressed = input
+        .dedup()
+        .collect_vec();
+    let corners = tiles
         .iter()
-        .map(|c| {
-            let x_idx = x_compressed.binary_search(&c.0).expect("could not find x");
-            let y_idx = y_compressed.binary_search(&c.1).expect("could not find y");
-            CompressedTile((x_idx, y_idx), *c)
-        })
+        .map(|&(x, y)| (compress(&xs, x), compress(&ys, y)))
         .collect_vec();
+    let (w, h) = (2 * xs.len() + 1, 2 * ys.len() + 1);
 
-    let mut grid = vec![vec![UNKNOWN; y_compressed.len()]; x_compressed.len()];
-    let mut to_check: VecDeque<(usize, usize)> = VecDeque::from([(0, 0)]);
-
-    for i in 0..num_corners {
-        let (x1, y1) = *compressed[i].compressed();
-        let (x2, y2) = *compressed[(i + 1) % num_corners].compressed();
-
+    let mut grid = vec![vec![UNKNOWN; h]; w];
+    for (&(x1, y1), &(x2, y2)) in corners.iter().circular_tuple_windows() {
         for row in grid[x1.min(x2)..=x1.max(x2)].iter_mut() {
             for cell in row[y1.min(y2)..=y1.max(y2)].iter_mut() {
                 *cell = INSIDE;
@@ -80,20 +136,16 @@ pub fn part2(input: &Input) -> u64 {
         }
     }
 
-    let w = grid.len();
-    let h = grid[0].len();
+    // The gaps before the first coordinates are outside the loop.
+    grid[0][0] = OUTSIDE;
+    let mut to_check: VecDeque<(usize, usize)> = VecDeque::from([(0, 0)]);
     while let Some(point) = to_check.pop_front() {
         let offsets: [(isize, isize); 4] = [(0, 1), (1, 0), (-1, 0), (0, -1)];
         let neighbors = offsets.iter().filter_map(|offset| {
             let x = point.0.checked_add_signed(offset.0)?;
             let y = point.1.checked_add_signed(offset.1)?;
-            if x >= w || y >= h {
-                return None;
-            }
-            Some((x, y))
+            (x < w && y < h).then_some((x, y))
         });
-
-        // TODO: Assert inside grid
         for n in neighbors {
             if grid[n.0][n.1] == UNKNOWN {
                 grid[n.0][n.1] = OUTSIDE;
@@ -102,46 +154,179 @@ pub fn part2(input: &Input) -> u64 {
         }
     }
 
-    for y in 1..h {
-        for x in 1..w {
-            let v = i32::from(grid[x][y] != OUTSIDE);
-            let new_v =
-                v + grid[x][y - 1] as i32 + grid[x - 1][y] as i32 - grid[x - 1][y - 1] as i32;
-            grid[x][y] = new_v;
+    // How many cells below and left of each cell, exclusive, are not outside.
+    let mut covered = vec![vec![0u32; h + 1]; w + 1];
+    for (x, row) in grid.iter().enumerate() {
+        for (y, &cell) in row.iter().enumerate() {
+            covered[x + 1][y + 1] =
+                u32::from(cell != OUTSIDE) + covered[x][y + 1] + covered[x + 1][y] - covered[x][y];
         }
     }
 
-    let mut max_area = 0;
-    for i in 0..num_corners {
-        for j in i + 1..num_corners {
-            let (x1, y1) = *compressed[i].compressed();
-            let (x2, y2) = *compressed[j].compressed();
-
-            let x3 = x1.min(x2);
-            let x4 = x1.max(x2);
-            let y3 = y1.min(y2);
-            let y4 = y1.max(y2);
+    largest_fitting(tiles, |i, j| {
+        let (x1, x2) = (
+            corners[i].0.min(corners[j].0),
+            corners[i].0.max(corners[j].0),
+        );
+        let (y1, y2) = (
+            corners[i].1.min(corners[j].1),
+            corners[i].1.max(corners[j].1),
+        );
+        let expected = (x2 - x1 + 1) * (y2 - y1 + 1);
+        let actual =
+            covered[x2 + 1][y2 + 1] + covered[x1][y1] - covered[x1][y2 + 1] - covered[x2 + 1][y1];
+        actual as usize == expected
+    })
+}
 
-            let expected = (x4 - x3 + 1) as i32 * (y4 - y3 + 1) as i32;
-            let actual = grid[x4][y4] - grid[x3 - 1][y4] - grid[x4][y3 - 1] + grid[x3 - 1][y3 - 1];
+/// The parts of each horizontal line through a corner that lie within the
+/// loop.
+struct Coverage {
+    /// The line through each corner.
+    lines: Vec<usize>,
+    /// The closed `x` intervals of each line within the loop, sorted and
+    /// disjoint.
+    intervals: Vec<Vec<(u64, u64)>>,
+}
 
-            if expected == actual {
-                let (x1, y1) = compressed[i].tile();
-                let (x2, y2) = compressed[j].tile();
-                let dx = x1.abs_diff(*x2) + 1;
-                let dy = y1.abs_diff(*y2) + 1;
-                max_area = max_area.max(dx * dy);
+impl Coverage {
+    /// Sweeps a horizontal line up the loop of `corners`, keeping the
+    /// vertical edges it crosses. Just above a line the loop is between
+    /// alternate edges, and the line itself is within the loop where the
+    /// loop is just above or just below it.
+    fn new(corners: &[Tile]) -> Self {
+        // Each vertical edge as its `x` and `y` range.
+        let edges = corners
+            .iter()
+            .circular_tuple_windows()
+            .filter(|(a, b)| a.0 == b.0 && a.1 != b.1)
+            .map(|(a, b)| (a.0, a.1.min(b.1), a.1.max(b.1)))
+            .collect_vec();
+        let ys = corners
+            .iter()
+            .map(|c| c.1)
+            .sorted_unstable()
+            .dedup()
+            .collect_vec();
+        let starts = edges
+            .iter()
+            .enumerate()
+            .sorted_by_key(|(_, e)| e.1)
+            .collect_vec();
+        let ends = edges
+            .iter()
+            .enumerate()
+            .sorted_by_key(|(_, e)| e.2)
+            .collect_vec();
+        let (mut next_start, mut next_end) = (0, 0);
+
+        let mut crossed = BTreeSet::new();
+        let mut intervals = Vec::with_capacity(ys.len());
+        for &y in &ys {
+            let below = Self::between_edges(&crossed);
+            while let Some(&(i, &(x, _, _))) = ends.get(next_end).filter(|(_, e)| e.2 == y) {
+                crossed.remove(&(x, i));
+                next_end += 1;
             }
+            while let Some(&(i, &(x, _, _))) = starts.get(next_start).filter(|(_, e)| e.1 == y) {
+                crossed.insert((x, i));
+                next_start += 1;
+            }
+            let above = Self::between_edges(&crossed);
+
+            let mut merged: Vec<(u64, u64)> = Vec::with_capacity(below.len() + above.len());
+            for (start, end) in below.into_iter().merge(above) {
+                match merged.last_mut() {
+                    Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                    _ => merged.push((start, end)),
+                }
+            }
+            intervals.push(merged);
         }
+        let lines = corners
+            .iter()
+            .map(|c| ys.binary_search(&c.1).expect("a corner coordinate"))
+            .collect();
+        Self { lines, intervals }
+    }
+
+    /// The intervals between alternate edges of those `crossed` by a line.
+    fn between_edges(crossed: &BTreeSet<(u64, usize)>) -> Vec<(u64, u64)> {
+        crossed.iter().tuples().map(|(a, b)| (a.0, b.0)).collect()
     }
 
-    max_area
+    /// Whether the line through corner `i` is within the loop from `x1` to
+    /// `x2`.
+    fn covers(&self, i: usize, x1: u64, x2: u64) -> bool {
+        let line = &self.intervals[self.lines[i]];
+        let next = line.partition_point(|&(start, _)| start <= x1);
+        next > 0 && line[next - 1].1 >= x2
+    }
+}
+
+/// A rectangle is within the loop when its sides are, as the loop has no
+/// holes, and each side lies on a line through a corner.
+fn largest_inside_sweep(tiles: &[Tile]) -> Option<(Tile, Tile)> {
+    let rows = Coverage::new(tiles);
+    let transposed = tiles.iter().map(|&(x, y)| (y, x)).collect_vec();
+    let columns = Coverage::new(&transposed);
+
+    largest_fitting(tiles, |i, j| {
+        let (a, b) = (tiles[i], tiles[j]);
+        let (x1, x2) = (a.0.min(b.0), a.0.max(b.0));
+        let (y1, y2) = (a.1.min(b.1), a.1.max(b.1));
+        rows.covers(i, x1, x2)
+            && rows.covers(j, x1, x2)
+            && columns.covers(i, y1, y2)
+            && columns.covers(j, y1, y2)
+    })
+}
+
+pub fn part2(input: &Input) -> u128 {
+    largest_inside_rectangle(&input.tiles, input.strategy).map_or(0, |(a, b)| area(&a, &b))
+}
+
+/// Draws the tile floor scaled to fit 512 pixels: the loop of red and green
+/// tiles, then the largest rectangle of part 1 in red and of part 2 in blue.
+pub fn visualize(input: &Input, recorder: &mut Recorder) -> std::io::Result<()> {
+    const SIZE: usize = 512;
+    let max = input
+        .tiles
+        .iter()
+        .map(|&(x, y)| x.max(y))
+        .max()
+        .unwrap_or(0);
+    let scale = |(x, y): Tile| {
+        let scale = |v: u64| (v as u128 * (SIZE as u128 - 1) / max.max(1) as u128) as usize;
+        (scale(x), scale(y))
+    };
+
+    let mut frame = Frame::new(SIZE, SIZE, Rgb::BLACK);
+    for (&a, &b) in input.tiles.iter().circular_tuple_windows() {
+        frame.line(scale(a), scale(b), Rgb::GREEN);
+    }
+    recorder.record(&frame)?;
+
+    for (rectangle, color) in [
+        (largest_rectangle(&input.tiles), Rgb::RED),
+        (
+            largest_inside_rectangle(&input.tiles, input.strategy),
+            Rgb::BLUE,
+        ),
+    ] {
+        if let Some((a, b)) = rectangle {
+            frame.rect(scale(a), scale(b), color);
+            recorder.record(&frame)?;
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rstest::rstest;
+    use aoc_utils::aoc_test;
+    use aoc_utils::cli::Generate;
 
     const SAMPLE_INPUT: &str = "7,1
 11,1
@@ -153,6 +338,21 @@ mod tests {
 7,3
 ";
 
+    aoc_test!(
+        day09,
+        example = SAMPLE_INPUT,
+        part1 = 50,
+        part2 = 24,
+        params = {}
+    );
+    aoc_test!(
+        day09,
+        name = sweep,
+        example = SAMPLE_INPUT,
+        part2 = 24,
+        params = { strategy: Strategy::Sweep }
+    );
+
     #[test]
     fn test_process() {
         let expected = vec![
@@ -165,47 +365,80 @@ mod tests {
             (2, 3),
             (7, 3),
         ];
-        let actual = process(SAMPLE_INPUT);
-        assert_eq!(expected, actual);
+        let actual = process(SAMPLE_INPUT, &Params::default()).unwrap();
+        assert_eq!(expected, actual.tiles);
     }
 
-    #[rstest]
-    fn test_part1() {
-        let input = process(SAMPLE_INPUT);
-        let expected = 50;
-        let actual = part1(&input);
-        assert_eq!(expected, actual);
+    #[test]
+    fn test_notches() {
+        // A U whose notch has no corners inside it, and a loop with a gap
+        // narrower than a tile.
+        let notch = [
+            (1, 1),
+            (11, 1),
+            (11, 11),
+            (8, 11),
+            (8, 3),
+            (4, 3),
+            (4, 11),
+            (1, 11),
+        ];
+        let gap = [
+            (1, 1),
+            (5, 1),
+            (5, 5),
+            (4, 5),
+            (4, 2),
+            (3, 2),
+            (3, 5),
+            (1, 5),
+        ];
+        for strategy in [Strategy::Grid, Strategy::Sweep] {
+            assert_eq!(
+                largest_inside_rectangle(&notch, strategy),
+                Some(((1, 1), (4, 11))),
+                "{:?}",
+                strategy
+            );
+            assert_eq!(
+                largest_inside_rectangle(&gap, strategy),
+                Some(((1, 1), (3, 5))),
+                "{:?}",
+                strategy
+            );
+        }
     }
 
-    #[rstest]
-    fn test_part2() {
-        let input = process(SAMPLE_INPUT);
-        let expected = 24;
-        let actual = part2(&input);
-        assert_eq!(expected, actual);
+    #[test]
+    fn test_strategies_agree() {
+        for seed in 0..20 {
+            let input = crate::generate::generate(&Generate {
+                year: 2025,
+                day: 9,
+                size: Some(4 + 12 * seed as usize),
+                seed,
+            })
+            .unwrap();
+            let tiles = parse::parse_input(&input).unwrap();
+            assert_eq!(
+                largest_inside_rectangle(&tiles, Strategy::Grid),
+                largest_inside_rectangle(&tiles, Strategy::Sweep),
+                "seed {}",
+                seed
+            );
+        }
     }
 }
 
 mod parse {
-    use winnow::{
-        Parser, Result,
-        ascii::{digit1, multispace0, newline},
-        combinator::{separated, separated_pair, terminated},
-    };
+    use aoc_utils::ints::lines_of;
+    use aoc_utils::parse::ParseReport;
 
     use crate::day09::Tile;
 
-    #[inline]
-    fn corner(input: &mut &str) -> Result<Tile> {
-        separated_pair(digit1.parse_to(), ',', digit1.parse_to()).parse_next(input)
-    }
-
-    fn parser(input: &mut &str) -> Result<Vec<Tile>> {
-        terminated(separated(1.., corner, newline), multispace0).parse_next(input)
-    }
-
-    pub fn parse_input(input: &str) -> Vec<Tile> {
-        parser.parse(input).expect("could not parse input")
+    pub fn parse_input(input: &str) -> Result<Vec<Tile>, ParseReport> {
+        let tiles = lines_of::<[u64; 2]>(input)?;
+        Ok(tiles.into_iter().map(|[x, y]| (x, y)).collect())
     }
 
     #[cfg(test)]
@@ -218,7 +451,7 @@ mod parse {
 123,123
 ";
             let expected = vec![(123, 123), (123, 123)];
-            let actual = parser.parse(input).unwrap();
+            let actual = parse_input(input).unwrap();
             assert_eq!(expected, actual);
         }
     }
@@ -1,54 +1,65 @@
-use itertools::Itertools;
+use std::fmt;
 
-type Input = Vec<Manual>;
+use aoc_utils::bitset::BitSet;
+use aoc_utils::gf2::{BitMatrix, MinWeightError};
+use aoc_utils::parse::ParseReport;
 
-#[derive(Debug, PartialEq)]
-struct Lights {
-    repr: u32,
-    size: usize,
-}
-
-impl Lights {
-    fn at(&self, index: usize) -> bool {
-        let bit = self.repr >> index & 1;
-        bit == 1
-    }
-}
+type Input = Vec<Manual>;
 
 #[derive(Debug, PartialEq)]
 pub struct Manual {
-    lights: Lights,
-    buttons: Vec<Lights>,
+    lights: BitSet,
+    buttons: Vec<BitSet>,
     joltage: Vec<u64>,
 }
 
-pub fn process(input: &str) -> Input {
-    parse::parse_input(input)
+/// Why a part has no answer for one of the manuals.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolveError {
+    Lights { line: usize, cause: MinWeightError },
+    Joltage { line: usize },
 }
 
-fn solve_lights_contrained(lights: &Lights, buttons: &[Lights]) -> Option<u64> {
-    for i in 0..buttons.len() {
-        let combinations = buttons.iter().combinations(i);
-        for comb in combinations {
-            let res = comb.iter().fold(
-                Lights {
-                    repr: 0,
-                    size: lights.size,
-                },
-                |acc, b| Lights {
-                    repr: acc.repr ^ b.repr,
-                    ..acc
-                },
-            );
-            if res == *lights {
-                return Some(comb.len() as u64);
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lights { line, cause } => {
+                write!(f, "lights of the manual on line {}: {}", line, cause)
+            }
+            Self::Joltage { line } => {
+                write!(f, "no presses reach the joltages on line {}", line)
             }
         }
     }
-    None
 }
 
-fn solve_joltage_constrained(buttons: &[Lights], joltages: &[u64]) -> Option<u64> {
+impl std::error::Error for SolveError {}
+
+pub fn process(input: &str) -> Result<Input, ParseReport> {
+    parse::parse_input(input)
+}
+
+fn solve_lights_contrained(lights: &BitSet, buttons: &[BitSet]) -> Result<u64, MinWeightError> {
+    let matrix = BitMatrix::from_columns(lights.len(), buttons);
+    let presses = matrix.min_weight_solution(lights)?;
+    Ok(presses.count_ones() as u64)
+}
+
+#[cfg(not(any(feature = "native-ilp", feature = "z3")))]
+compile_error!("day10 needs either the `native-ilp` or the `z3` feature");
+
+#[cfg(feature = "native-ilp")]
+fn solve_joltage_native(buttons: &[BitSet], joltages: &[u64]) -> Option<u64> {
+    let matrix = (0..joltages.len())
+        .map(|i| buttons.iter().map(|b| b.contains(i) as i64).collect())
+        .collect();
+    let targets = joltages.iter().map(|&j| j as i64).collect();
+    let presses = aoc_utils::ilp::LinearSystem::new(matrix, targets).minimize_sum()?;
+    Some(presses.iter().sum())
+}
+
+#[cfg(all(feature = "z3", any(test, not(feature = "native-ilp"))))]
+fn solve_joltage_z3(buttons: &[BitSet], joltages: &[u64]) -> Option<u64> {
     let solver = z3::Optimize::new();
     let vars: Vec<_> = (0..buttons.len())
         .map(|j| {
@@ -63,7 +74,7 @@ fn solve_joltage_constrained(buttons: &[Lights], joltages: &[u64]) -> Option<u64
         let joltage_sum = buttons
             .iter()
             .zip(vars.iter())
-            .map(|(b, v)| v * (b.at(i) as u64))
+            .map(|(b, v)| v * (b.contains(i) as u64))
             .sum::<z3::ast::Int>();
         solver.assert(&joltage_sum.eq(*target));
     }
@@ -75,117 +86,121 @@ fn solve_joltage_constrained(buttons: &[Lights], joltages: &[u64]) -> Option<u64
             let model = solver.get_model().expect("no model");
             model.eval(&objective, true)?.as_u64()
         }
-        _ => unreachable!(),
+        _ => None,
     }
 }
 
-pub fn part1(input: &Input) -> u64 {
+fn solve_joltage_constrained(buttons: &[BitSet], joltages: &[u64]) -> Option<u64> {
+    #[cfg(feature = "native-ilp")]
+    return solve_joltage_native(buttons, joltages);
+
+    #[cfg(not(feature = "native-ilp"))]
+    return solve_joltage_z3(buttons, joltages);
+}
+
+pub fn part1(input: &Input) -> Result<u64, SolveError> {
     input
         .iter()
-        .map(|manual| {
-            solve_lights_contrained(&manual.lights, &manual.buttons).expect("No solution found")
+        .enumerate()
+        .map(|(i, manual)| {
+            solve_lights_contrained(&manual.lights, &manual.buttons)
+                .map_err(|cause| SolveError::Lights { line: i + 1, cause })
         })
         .sum()
 }
 
-pub fn part2(input: &Input) -> u64 {
+pub fn part2(input: &Input) -> Result<u64, SolveError> {
     input
         .iter()
-        .map(|manual| {
-            solve_joltage_constrained(&manual.buttons, &manual.joltage).expect("No solution found")
+        .enumerate()
+        .map(|(i, manual)| {
+            solve_joltage_constrained(&manual.buttons, &manual.joltage)
+                .ok_or(SolveError::Joltage { line: i + 1 })
         })
-        .sum::<u64>()
+        .sum()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc_utils::aoc_test;
 
     const SAMPLE_INPUT: &str = "[.##.] (3) (1,3) (2) (2,3) (0,2) (0,1) {3,5,4,7}
 [...#.] (0,2,3,4) (2,3) (0,4) (0,1,2) (1,2,3,4) {7,5,12,7,2}
 [.###.#] (0,1,2,3,4) (0,3,4) (0,1,2,4,5) (1,2) {10,11,11,5,10,5}";
 
+    aoc_test!(day10, example = SAMPLE_INPUT, part1 = Ok(7), part2 = Ok(33));
+
     #[test]
-    fn test_part1() {
-        let input = process(SAMPLE_INPUT);
-        let actual = part1(&input);
-        let expected = 7;
-        assert_eq!(expected, actual);
+    fn test_unreachable() {
+        let input = process("[##] (0,1) {1,1}\n[.#] (0) {1,0}\n").unwrap();
+        let cause = MinWeightError::Inconsistent;
+        assert_eq!(part1(&input), Err(SolveError::Lights { line: 2, cause }));
+        assert_eq!(part2(&input), Ok(2));
+
+        let input = process("[##] (0,1) {1,2}\n").unwrap();
+        assert_eq!(part1(&input), Ok(1));
+        assert_eq!(part2(&input), Err(SolveError::Joltage { line: 1 }));
     }
 
+    #[cfg(all(feature = "native-ilp", feature = "z3"))]
     #[test]
-    fn test_part2() {
-        let input = process(SAMPLE_INPUT);
-        let actual = part2(&input);
-        let expected = 33;
-        assert_eq!(expected, actual);
+    fn test_joltage_backends_agree() {
+        for manual in process(SAMPLE_INPUT).unwrap() {
+            assert_eq!(
+                solve_joltage_native(&manual.buttons, &manual.joltage),
+                solve_joltage_z3(&manual.buttons, &manual.joltage),
+            );
+        }
     }
 }
 
 mod parse {
-    use crate::day10::{Lights, Manual};
-    use winnow::Parser as _;
-
-    fn lights(input: &mut &str) -> winnow::Result<Lights> {
-        let lights: Vec<_> = winnow::combinator::delimited(
-            '[',
-            winnow::combinator::repeat(
-                1..,
-                winnow::combinator::alt(('#'.value(true), '.'.value(false))),
-            ),
-            ']',
-        )
-        .parse_next(input)?;
-
-        let size = lights.len();
-        let lights =
-            lights
-                .into_iter()
-                .enumerate()
-                .fold(Lights { repr: 0, size }, |acc, (i, l)| {
-                    let repr = (l as u32) << i ^ acc.repr;
-                    Lights { repr, size }
-                });
-        Ok(lights)
+    use crate::day10::Manual;
+    use aoc_utils::bitset::BitSet;
+    use aoc_utils::parse::{
+        ParseReport, comma_separated, expected, label, lines, parse_all, space_separated, unsigned,
+    };
+    use winnow::{ModalResult, Parser as _};
+
+    fn lights(input: &mut &str) -> ModalResult<BitSet> {
+        winnow::combinator::delimited('[', winnow::token::take_while(1.., ['#', '.']), ']')
+            .take()
+            .parse_to()
+            .context(label("indicator lights"))
+            .parse_next(input)
     }
 
-    fn button(size: usize) -> impl FnMut(&mut &str) -> winnow::Result<Lights> {
+    fn button(size: usize) -> impl FnMut(&mut &str) -> ModalResult<BitSet> {
         move |input: &mut &str| {
-            let button: Vec<u32> = winnow::combinator::delimited(
-                '(',
-                winnow::combinator::separated(1.., winnow::ascii::digit1.parse_to::<u32>(), ','),
-                ')',
-            )
-            .parse_next(input)?;
-            let button = button.into_iter().fold(Lights { repr: 0, size }, |acc, b| {
-                let repr = 1 << b ^ acc.repr;
-                Lights { repr, size }
-            });
-            Ok(button)
+            winnow::combinator::delimited('(', comma_separated(unsigned), ')')
+                .verify_map(|button: Vec<usize>| BitSet::try_from_indices(size, button).ok())
+                .context(label("button"))
+                .context(expected("button wiring within the lights"))
+                .parse_next(input)
         }
     }
 
-    fn joltage(input: &mut &str) -> winnow::Result<Vec<u64>> {
-        winnow::combinator::delimited(
-            '{',
-            winnow::combinator::separated(1.., winnow::ascii::digit1.parse_to::<u64>(), ','),
-            '}',
-        )
-        .parse_next(input)
+    fn joltage(size: usize) -> impl FnMut(&mut &str) -> ModalResult<Vec<u64>> {
+        move |input: &mut &str| {
+            winnow::combinator::delimited('{', comma_separated(unsigned), '}')
+                .verify(|joltage: &Vec<u64>| joltage.len() == size)
+                .context(label("joltage requirements"))
+                .context(expected("one joltage per light"))
+                .parse_next(input)
+        }
     }
 
-    fn manual(input: &mut &str) -> winnow::Result<Manual> {
+    fn manual(input: &mut &str) -> ModalResult<Manual> {
         let lights = lights.parse_next(input)?;
 
         winnow::ascii::space1.parse_next(input)?;
 
-        let buttons: Vec<Lights> =
-            winnow::combinator::separated(1.., button(lights.size), winnow::ascii::space1)
-                .parse_next(input)?;
+        let buttons = space_separated(button(lights.len())).parse_next(input)?;
 
         winnow::ascii::space1.parse_next(input)?;
 
-        let joltage = joltage.parse_next(input)?;
+        let joltage = joltage(lights.len()).parse_next(input)?;
 
         Ok(Manual {
             lights,
@@ -194,15 +209,12 @@ mod parse {
         })
     }
 
-    fn parser(input: &mut &str) -> winnow::Result<Vec<Manual>> {
-        let manuals =
-            winnow::combinator::separated(1.., manual, winnow::ascii::newline).parse_next(input)?;
-        winnow::ascii::multispace0.parse_next(input)?;
-        Ok(manuals)
+    fn parser(input: &mut &str) -> ModalResult<Vec<Manual>> {
+        lines(manual).parse_next(input)
     }
 
-    pub fn parse_input(input: &str) -> Vec<Manual> {
-        parser.parse(input).expect("could not parse input")
+    pub fn parse_input(input: &str) -> Result<Vec<Manual>, ParseReport> {
+        parse_all(parser, input)
     }
 
     #[cfg(test)]
@@ -214,41 +226,34 @@ mod parse {
             let input = "[###.] (3) (1,3) (2) (2,3) (0,2) (0,1) {3,5,4,7}
 ";
 
+            let bits = |s: &str| s.parse::<BitSet>().unwrap();
             let expected = vec![Manual {
-                lights: Lights {
-                    repr: 0b0111,
-                    size: 4,
-                },
+                lights: bits("[###.]"),
                 buttons: vec![
-                    Lights {
-                        repr: 0b1000,
-                        size: 4,
-                    },
-                    Lights {
-                        repr: 0b1010,
-                        size: 4,
-                    },
-                    Lights {
-                        repr: 0b0100,
-                        size: 4,
-                    },
-                    Lights {
-                        repr: 0b1100,
-                        size: 4,
-                    },
-                    Lights {
-                        repr: 0b0101,
-                        size: 4,
-                    },
-                    Lights {
-                        repr: 0b0011,
-                        size: 4,
-                    },
+                    bits("[...#]"),
+                    bits("[.#.#]"),
+                    bits("[..#.]"),
+                    bits("[..##]"),
+                    bits("[#.#.]"),
+                    bits("[##..]"),
                 ],
                 joltage: vec![3, 5, 4, 7],
             }];
-            let actual = parse_input(input);
+            let actual = parse_input(input).unwrap();
             assert_eq!(expected, actual);
         }
+
+        #[test]
+        fn test_button_out_of_range() {
+            let input = "[.#] (0,2) {1,1}\n";
+            assert!(parse_all(parser, input).is_err());
+        }
+
+        #[test]
+        fn test_joltage_count() {
+            let report = parse_input("[##.] (0,1) {3,5,4,7}\n").unwrap_err();
+            assert_eq!((report.line, report.column), (1, 13));
+            assert_eq!(report.expected, vec!["one joltage per light"]);
+        }
     }
 }
@@ -1,9 +1,14 @@
+use std::fmt;
+
+use aoc_utils::bigint::BigUint;
+use aoc_utils::parse::ParseReport;
+
 pub type Input = Graph;
 
 #[derive(Debug)]
 struct NodeIndexer {
     mapping: Vec<Option<usize>>,
-    size: usize,
+    names: Vec<String>,
 }
 
 #[inline]
@@ -16,7 +21,10 @@ fn node_to_index(node: &str) -> usize {
 impl NodeIndexer {
     fn new() -> Self {
         let mapping = vec![None; 26usize.pow(3)];
-        Self { mapping, size: 0 }
+        Self {
+            mapping,
+            names: vec![],
+        }
     }
 
     #[inline]
@@ -25,16 +33,19 @@ impl NodeIndexer {
         if let Some(mapped) = self.mapping[idx] {
             return mapped;
         }
-        let prev_size = self.size;
-        self.size += 1;
-        self.mapping[idx] = Some(prev_size);
-        prev_size
+        let index = self.names.len();
+        self.names.push(node.to_owned());
+        self.mapping[idx] = Some(index);
+        index
     }
 
+    /// The index of `node`, which need not be a valid device name.
     #[inline]
     fn index_of(&self, node: &str) -> Option<usize> {
-        let idx = node_to_index(node);
-        self.mapping[idx]
+        if node.len() != 3 || !node.bytes().all(|b| b.is_ascii_lowercase()) {
+            return None;
+        }
+        self.mapping[node_to_index(node)]
     }
 }
 
@@ -46,73 +57,251 @@ pub struct Graph {
     indexer: NodeIndexer,
 }
 
-pub fn process(input: &'_ str) -> Input {
-    let lines: Vec<_> = input.lines().filter(|line| !line.is_empty()).collect();
+/// Devices that lead back to the first, in order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cycle(pub Vec<String>);
 
-    let mut mapping = vec![vec![]; lines.len() + 1];
-    let mut indexer = NodeIndexer::new();
-    for line in lines {
-        let mut nodes = line.split_ascii_whitespace();
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for name in &self.0 {
+            write!(f, "{} -> ", name)?;
+        }
+        write!(f, "{}", self.0[0])
+    }
+}
 
-        let from = nodes.next().expect("no start node");
-        let from = indexer.get_or_create_index(from);
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PathError {
+    UnknownDevice(String),
+    /// Paths can go around the cycle any number of times.
+    Cycle(Cycle),
+}
 
-        let to: Vec<_> = nodes
-            .map(|node_str| indexer.get_or_create_index(node_str))
-            .collect();
-        mapping[from] = to;
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownDevice(name) => write!(f, "no device named {}", name),
+            Self::Cycle(cycle) => write!(f, "infinitely many paths around {}", cycle),
+        }
     }
-
-    Graph { mapping, indexer }
 }
 
-fn dfs(start: usize, end: usize, mapping: &Mapping, cache: &mut [Option<u64>]) -> u64 {
-    if let Some(cached) = cache[start] {
-        return cached;
+impl std::error::Error for PathError {}
+
+impl Graph {
+    fn index_of(&self, name: &str) -> Result<usize, PathError> {
+        self.indexer
+            .index_of(name)
+            .ok_or_else(|| PathError::UnknownDevice(name.to_owned()))
     }
 
-    if start == end {
-        cache[start] = Some(1);
-        return 1;
+    /// The nodes reachable from `root` through nodes that are `allowed`,
+    /// each before its outputs, or the indices of a cycle among them.
+    ///
+    /// Searches depth first with an explicit stack, so long chains of devices
+    /// cannot overflow the call stack.
+    fn topological_order(&self, root: usize, allowed: &[bool]) -> Result<Vec<usize>, Vec<usize>> {
+        const NEW: u8 = 0;
+        const OPEN: u8 = 1;
+        const DONE: u8 = 2;
+
+        let mut state = vec![NEW; self.mapping.len()];
+        let mut order = Vec::with_capacity(self.mapping.len());
+        if !allowed[root] {
+            return Ok(order);
+        }
+        state[root] = OPEN;
+        // Each open node with how many of its outputs were followed.
+        let mut stack = vec![(root, 0)];
+        while let Some((node, next)) = stack.last_mut() {
+            let node = *node;
+            let Some(&output) = self.mapping[node].get(*next) else {
+                state[node] = DONE;
+                order.push(node);
+                stack.pop();
+                continue;
+            };
+            *next += 1;
+            if !allowed[output] {
+                continue;
+            }
+            match state[output] {
+                NEW => {
+                    state[output] = OPEN;
+                    stack.push((output, 0));
+                }
+                OPEN => {
+                    let start = stack.iter().position(|&(n, _)| n == output).unwrap();
+                    return Err(stack[start..].iter().map(|&(n, _)| n).collect());
+                }
+                _ => {}
+            }
+        }
+        order.reverse();
+        Ok(order)
     }
 
-    let sum = mapping[start]
-        .iter()
-        .map(|t| dfs(*t, end, mapping, cache))
-        .sum::<u64>();
-    cache[start] = Some(sum);
-    sum
+    /// The devices on paths from `from` to `to`, each before its outputs,
+    /// and which devices lead to `to`, or the indices of a cycle on such a
+    /// path.
+    fn between(&self, from: usize, to: usize) -> Result<(Vec<usize>, Vec<bool>), Vec<usize>> {
+        let mut inputs = vec![vec![]; self.mapping.len()];
+        for (node, outputs) in self.mapping.iter().enumerate() {
+            for &output in outputs {
+                inputs[output].push(node);
+            }
+        }
+        let mut leads_to = vec![false; self.mapping.len()];
+        leads_to[to] = true;
+        let mut to_check = vec![to];
+        while let Some(node) = to_check.pop() {
+            for &input in &inputs[node] {
+                if !leads_to[input] {
+                    leads_to[input] = true;
+                    to_check.push(input);
+                }
+            }
+        }
+        let order = self.topological_order(from, &leads_to)?;
+        Ok((order, leads_to))
+    }
+
+    fn cycle(&self, nodes: Vec<usize>) -> Cycle {
+        Cycle(
+            nodes
+                .into_iter()
+                .map(|n| self.indexer.names[n].clone())
+                .collect(),
+        )
+    }
+
+    /// Counts the paths from `from` to `to` that visit all of `waypoints`,
+    /// in any order.
+    ///
+    /// Only devices between `from` and `to` are considered, so cycles
+    /// elsewhere are fine. Each of them keeps a count for every subset of the
+    /// waypoints, which costs `2^k` counts per device for `k` waypoints.
+    ///
+    /// # Panics
+    ///
+    /// If there are more waypoints than bits in a `usize`.
+    pub fn count_paths(
+        &self,
+        from: &str,
+        to: &str,
+        waypoints: &[&str],
+    ) -> Result<BigUint, PathError> {
+        assert!(waypoints.len() < usize::BITS as usize, "too many waypoints");
+        let (from, to) = (self.index_of(from)?, self.index_of(to)?);
+        let mut bits = vec![0usize; self.mapping.len()];
+        for (i, waypoint) in waypoints.iter().enumerate() {
+            bits[self.index_of(waypoint)?] |= 1 << i;
+        }
+        let all = (1 << waypoints.len()) - 1;
+
+        let (order, leads_to) = self
+            .between(from, to)
+            .map_err(|cycle| PathError::Cycle(self.cycle(cycle)))?;
+
+        // The paths from `from` to each device by the waypoints they visit.
+        let mut counts = vec![vec![]; self.mapping.len()];
+        if leads_to[from] {
+            counts[from] = vec![BigUint::zero(); all + 1];
+            counts[from][bits[from]] = BigUint::from(1u64);
+        }
+        for node in order {
+            let paths = std::mem::take(&mut counts[node]);
+            if node == to {
+                return Ok(paths[all].clone());
+            }
+            for &output in self.mapping[node].iter().filter(|&&o| leads_to[o]) {
+                let counts = &mut counts[output];
+                if counts.is_empty() {
+                    *counts = vec![BigUint::zero(); all + 1];
+                }
+                for (visited, count) in paths.iter().enumerate() {
+                    if !count.is_zero() {
+                        counts[visited | bits[output]] += count;
+                    }
+                }
+            }
+        }
+        Ok(BigUint::zero())
+    }
 }
 
-fn find_path(from: &str, to: &str, graph: &Graph) -> u64 {
-    let indexer = &graph.indexer;
-    let mut cache = vec![None; graph.mapping.len()];
-    dfs(
-        indexer.index_of(from).expect("from not found"),
-        indexer.index_of(to).expect("to not found"),
-        &graph.mapping,
-        &mut cache,
-    )
+/// The devices the paths of each part go between.
+const QUERIES: [(&str, &str); 2] = [("you", "out"), ("svr", "out")];
+
+pub fn process(input: &'_ str) -> Result<Input, ParseReport> {
+    let lines = parse::parse_input(input)?;
+
+    let mut mapping = vec![vec![]; lines.len() + 1];
+    // The line listing the outputs of each device.
+    let mut line_of = vec![None; lines.len() + 1];
+    let mut indexer = NodeIndexer::new();
+    for (line, (from, to)) in lines.into_iter().enumerate() {
+        let from = indexer.get_or_create_index(from);
+        let to: Vec<_> = to
+            .into_iter()
+            .map(|node_str| indexer.get_or_create_index(node_str))
+            .collect();
+        // Outputs that never have a line of their own still need a node.
+        mapping.resize(mapping.len().max(indexer.names.len()), vec![]);
+        line_of.resize(mapping.len(), None);
+        mapping[from] = to;
+        line_of[from] = Some(line);
+    }
+    let graph = Graph { mapping, indexer };
+
+    // Reject cycles on the paths the parts count, so that they cannot fail.
+    for (from, to) in QUERIES {
+        let (Some(from), Some(to)) = (graph.indexer.index_of(from), graph.indexer.index_of(to))
+        else {
+            continue;
+        };
+        if let Err(cycle) = graph.between(from, to) {
+            // Point at the output that closes the cycle.
+            let last = cycle[cycle.len() - 1];
+            let line = line_of[last].expect("devices in a cycle have outputs");
+            let start: usize = input.split_inclusive('\n').take(line).map(str::len).sum();
+            let text = input[start..].lines().next().unwrap_or_default();
+            let first = &graph.indexer.names[cycle[0]];
+            let column = text.find(':').unwrap_or(0);
+            let column = column + text[column..].find(first.as_str()).unwrap_or(0);
+            let cycle = graph.cycle(cycle);
+            return Err(ParseReport::expected(
+                input,
+                start + column,
+                format!("an output that does not close the cycle {}", cycle),
+            ));
+        }
+    }
+    Ok(graph)
 }
 
-pub fn part1(input: &Input) -> u64 {
-    find_path("you", "out", input)
+/// Counts paths between devices of the input, whose cycles `process` has
+/// ruled out. There are no paths through a device the input lacks.
+fn count_paths(graph: &Graph, from: &str, to: &str, waypoints: &[&str]) -> BigUint {
+    match graph.count_paths(from, to, waypoints) {
+        Ok(count) => count,
+        Err(PathError::UnknownDevice(_)) => BigUint::zero(),
+        Err(err) => panic!("{}", err),
+    }
 }
 
-pub fn part2(input: &Input) -> u64 {
-    let svr_fft = find_path("svr", "fft", input);
-    let svr_dac = find_path("svr", "dac", input);
-    let dac_fft = find_path("dac", "fft", input);
-    let fft_dac = find_path("fft", "dac", input);
-    let dac_out = find_path("dac", "out", input);
-    let fft_out = find_path("fft", "out", input);
+pub fn part1(input: &Input) -> BigUint {
+    count_paths(input, "you", "out", &[])
+}
 
-    svr_fft * fft_dac * dac_out + svr_dac * dac_fft * fft_out
+pub fn part2(input: &Input) -> BigUint {
+    count_paths(input, "svr", "out", &["dac", "fft"])
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc_utils::aoc_test;
 
     const SAMPLE_INPUT: &str = "aaa: you hhh
 you: bbb ccc
@@ -125,11 +314,150 @@ ggg: out
 hhh: ccc fff iii
 iii: out";
 
+    const SAMPLE_INPUT_PART2: &str = "svr: aaa bbb
+aaa: fft
+fft: ccc
+bbb: tty
+tty: ccc
+ccc: ddd eee
+ddd: hub
+hub: fff
+eee: dac
+dac: fff
+fff: ggg hhh
+ggg: out
+hhh: out";
+
+    aoc_test!(day11, example = SAMPLE_INPUT, part1 = BigUint::from(5u64));
+    aoc_test!(
+        day11,
+        name = example_part2,
+        example = SAMPLE_INPUT_PART2,
+        part2 = BigUint::from(2u64)
+    );
+
+    #[test]
+    fn test_outputs_without_lines() {
+        let graph = process("you: aaa bbb ccc\naaa: out\n").unwrap();
+        assert_eq!(part1(&graph), BigUint::from(1u64));
+        assert_eq!(part2(&graph), BigUint::zero());
+
+        let report = process("you: Out\n").unwrap_err();
+        assert_eq!((report.line, report.column), (1, 6));
+        assert_eq!(report.expected, vec!["three letter device name"]);
+    }
+
+    /// A three letter name for each index.
+    fn name(i: usize) -> String {
+        [i / 676, i / 26, i]
+            .map(|d| char::from(b'a' + (d % 26) as u8))
+            .iter()
+            .collect()
+    }
+
+    #[test]
+    fn test_waypoints() {
+        let graph = process(SAMPLE_INPUT_PART2).unwrap();
+        let count = |from, to, waypoints: &[&str]| {
+            graph
+                .count_paths(from, to, waypoints)
+                .unwrap()
+                .to_u128()
+                .unwrap()
+        };
+        assert_eq!(count("svr", "out", &[]), 8);
+        assert_eq!(count("svr", "out", &["fft"]), 4);
+        assert_eq!(count("svr", "out", &["fft", "dac", "fft"]), 2);
+        assert_eq!(count("svr", "out", &["svr", "out"]), 8);
+        assert_eq!(count("svr", "svr", &[]), 1);
+        assert_eq!(count("out", "svr", &[]), 0);
+        assert_eq!(count("aaa", "out", &["tty"]), 0);
+        for name in ["xyz", "OUT", "outs", "ou", ""] {
+            assert_eq!(
+                graph.count_paths("svr", name, &[]),
+                Err(PathError::UnknownDevice(name.to_owned()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_cycles() {
+        // The cycle is on the way from `svr` to `out`.
+        let input = "svr: aaa out\naaa: bbb\nbbb: ccc\nccc: aaa out\n";
+        let report = process(input).unwrap_err();
+        assert_eq!((report.line, report.column), (4, 6));
+        assert_eq!(
+            report.expected,
+            vec!["an output that does not close the cycle aaa -> bbb -> ccc -> aaa"]
+        );
+
+        // Here it never reaches `out`, so only paths into it are infinite.
+        let input = "svr: aaa out\naaa: bbb\nbbb: ccc\nccc: aaa\n";
+        let graph = process(input).unwrap();
+        assert_eq!(
+            graph.count_paths("svr", "out", &[]),
+            Ok(BigUint::from(1u64))
+        );
+        assert_eq!(
+            graph
+                .count_paths("svr", "ccc", &[])
+                .unwrap_err()
+                .to_string(),
+            "infinitely many paths around aaa -> bbb -> ccc -> aaa"
+        );
+    }
+
     #[test]
-    fn test_part1() {
-        let input = process(SAMPLE_INPUT);
-        let actual = part1(&input);
-        let expected = 5;
-        assert_eq!(actual, expected);
+    fn test_long_and_dense() {
+        // A chain of every three letter name, too deep for a recursive search.
+        let names: Vec<_> = (0..26 * 26 * 26).map(name).collect();
+        let chain: String = names
+            .windows(2)
+            .map(|pair| format!("{}: {}\n", pair[0], pair[1]))
+            .collect();
+        let graph = process(&chain).unwrap();
+        let last = names.last().unwrap();
+        let count = graph.count_paths("aaa", last, &["mmm"]).unwrap();
+        assert_eq!(count, BigUint::from(1u64));
+
+        // Each level leads to both devices of the next, doubling the paths.
+        let levels = 200;
+        let mut ladder = String::new();
+        for level in 0..levels {
+            let next = [name(2 * level + 2), name(2 * level + 3)].join(" ");
+            ladder += &format!(
+                "{}: {}\n{}: {}\n",
+                name(2 * level),
+                next,
+                name(2 * level + 1),
+                next
+            );
+        }
+        let graph = process(&ladder).unwrap();
+        let count = graph
+            .count_paths("aaa", &name(2 * levels), &[&name(3)])
+            .unwrap();
+        // Past the waypoint every level but the last doubles the paths, so
+        // there are `2^(levels - 2)`.
+        assert_eq!(count.bits(), levels as u64 - 1);
+        assert_eq!(count.to_u128(), None);
+    }
+}
+
+mod parse {
+    use aoc_utils::parse::{
+        ParseReport, expected, key_value, label, lines, parse_all, space_separated,
+    };
+    use winnow::{ModalResult, Parser, token::take_while};
+
+    fn name<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+        take_while(3, 'a'..='z')
+            .context(expected("three letter device name"))
+            .parse_next(input)
+    }
+
+    pub fn parse_input(input: &str) -> Result<Vec<(&str, Vec<&str>)>, ParseReport> {
+        let device = key_value(name, space_separated(name)).context(label("device"));
+        parse_all(lines(device), input)
     }
 }
@@ -1,29 +1,324 @@
-type Input = u32;
+use aoc_utils::dlx::ExactCover;
+use aoc_utils::parse::ParseReport;
+use itertools::Itertools;
 
-const UPPER_BOUND: u32 = 9;
+pub type Input = (Vec<Shape>, Vec<Region>);
 
-pub fn process(input: &str) -> u32 {
-    let (_presents, objectives) = input.rsplit_once("\n\n").expect("Invalid input");
+type Cell = (usize, usize);
 
-    objectives
-        .lines()
-        .filter(|line| {
-            let (uni, n_presents) = line.split_once(": ").expect("Invalid line");
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shape {
+    cells: Vec<Cell>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Region {
+    width: usize,
+    height: usize,
+    counts: Vec<usize>,
+}
+
+impl Shape {
+    fn new(cells: impl IntoIterator<Item = Cell>) -> Self {
+        let cells = cells.into_iter().collect_vec();
+        let min_r = cells.iter().map(|c| c.0).min().unwrap_or(0);
+        let min_c = cells.iter().map(|c| c.1).min().unwrap_or(0);
+        let cells = cells
+            .into_iter()
+            .map(|(r, c)| (r - min_r, c - min_c))
+            .sorted_unstable()
+            .collect();
+        Self { cells }
+    }
+
+    #[inline]
+    fn area(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Height and width of the bounding box.
+    fn size(&self) -> (usize, usize) {
+        let h = self.cells.iter().map(|c| c.0 + 1).max().unwrap_or(0);
+        let w = self.cells.iter().map(|c| c.1 + 1).max().unwrap_or(0);
+        (h, w)
+    }
+
+    fn rotated(&self) -> Self {
+        let (h, _) = self.size();
+        Self::new(self.cells.iter().map(|&(r, c)| (c, h - 1 - r)))
+    }
+
+    fn flipped(&self) -> Self {
+        let (_, w) = self.size();
+        Self::new(self.cells.iter().map(|&(r, c)| (r, w - 1 - c)))
+    }
+
+    /// The distinct rotations and reflections of the shape.
+    fn orientations(&self) -> Vec<Shape> {
+        let mut orientations = vec![];
+        for start in [self.clone(), self.flipped()] {
+            let mut shape = start;
+            for _ in 0..4 {
+                let next = shape.rotated();
+                if !orientations.contains(&shape) {
+                    orientations.push(shape);
+                }
+                shape = next;
+            }
+        }
+        orientations
+    }
+}
+
+/// The packing of a region as an exact cover problem.
+///
+/// Every cell is a primary column, covered by a present or by a hole. Each
+/// shape is a secondary column with its count as capacity, and the holes
+/// share one with the region area minus the area of all presents. Covering
+/// every cell within those capacities therefore places every present.
+fn packing(shapes: &[Shape], region: &Region, holes: usize) -> ExactCover {
+    let (width, height) = (region.width, region.height);
+    let cells = width * height;
+    let mut problem = ExactCover::new(cells, shapes.len() + 1);
+    for (idx, (shape, &count)) in shapes.iter().zip(&region.counts).enumerate() {
+        if count == 0 {
+            continue;
+        }
+        problem.set_capacity(cells + idx, count);
+        for orientation in shape.orientations() {
+            let (h, w) = orientation.size();
+            if h > height || w > width {
+                continue;
+            }
+            for r in 0..=height - h {
+                for c in 0..=width - w {
+                    let covered = orientation
+                        .cells
+                        .iter()
+                        .map(|&(dr, dc)| (r + dr) * width + c + dc);
+                    problem.add_row(covered.chain([cells + idx]));
+                }
+            }
+        }
+    }
+    if holes > 0 {
+        problem.set_capacity(cells + shapes.len(), holes);
+        for cell in 0..cells {
+            problem.add_row([cell, cells + shapes.len()]);
+        }
+    }
+    problem
+}
 
-            let w = uni[0..2].parse::<u32>().expect("Invalid line");
-            let h = uni[3..5].parse::<u32>().expect("Invalid line");
-            let size = w * h;
+fn fits(shapes: &[Shape], region: &Region) -> bool {
+    let area = region.width.saturating_mul(region.height);
+    let present_area = shapes
+        .iter()
+        .zip(&region.counts)
+        .map(|(s, n)| s.area().saturating_mul(*n))
+        .fold(0, usize::saturating_add);
+    if present_area > area {
+        return false;
+    }
 
-            UPPER_BOUND
-                * n_presents
-                    .split_whitespace()
-                    .map(|n| n.parse::<u32>().expect("Invalid line"))
-                    .sum::<u32>()
-                <= size
+    // Presents side by side in their bounding boxes always fit.
+    let presents = region.counts.iter().copied().fold(0, usize::saturating_add);
+    let side = shapes
+        .iter()
+        .map(|s| {
+            let (h, w) = s.size();
+            h.max(w)
         })
-        .count() as u32
+        .max()
+        .unwrap_or(1);
+    let boxes = (region.width / side).checked_mul(region.height / side);
+    if boxes.is_none_or(|boxes| presents <= boxes) {
+        return true;
+    }
+
+    packing(shapes, region, area - present_area)
+        .first_solution()
+        .is_some()
+}
+
+pub fn process(input: &str) -> Result<Input, ParseReport> {
+    parse::parse_input(input)
 }
 
 pub fn part1(input: &Input) -> u32 {
-    *input
+    let (shapes, regions) = input;
+    regions.iter().filter(|region| fits(shapes, region)).count() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc_utils::aoc_test;
+
+    const SAMPLE_INPUT: &str = "0:
+###
+##.
+##.
+
+1:
+###
+##.
+.##
+
+2:
+.##
+###
+##.
+
+3:
+##.
+###
+##.
+
+4:
+###
+#..
+###
+
+5:
+###
+.#.
+###
+
+4x4: 0 0 0 0 2 0
+12x5: 1 0 1 0 2 2
+12x5: 1 0 1 0 3 2
+";
+
+    aoc_test!(day12, example = SAMPLE_INPUT, part1 = 2);
+
+    #[test]
+    fn test_orientations() {
+        let (shapes, _) = process(SAMPLE_INPUT).unwrap();
+        assert_eq!(shapes[0].orientations().len(), 8);
+        assert_eq!(shapes[4].orientations().len(), 4);
+        assert_eq!(shapes[5].orientations().len(), 2);
+    }
+
+    #[test]
+    fn test_fits() {
+        let (shapes, regions) = process(SAMPLE_INPUT).unwrap();
+        let actual = regions.iter().map(|r| fits(&shapes, r)).collect_vec();
+        assert_eq!(actual, vec![true, true, false]);
+    }
+
+    #[test]
+    fn test_tight_packing() {
+        // Dominoes around a hole, and L-trominoes that cannot tile a square.
+        let input = "0:\n##\n\n1:\n#.\n##\n\n3x3: 4 0\n3x3: 0 3\n2x3: 0 2\n";
+        let (shapes, regions) = process(input).unwrap();
+        let actual = regions.iter().map(|r| fits(&shapes, r)).collect_vec();
+        assert_eq!(actual, vec![true, false, true]);
+    }
+}
+
+mod parse {
+    use super::{Region, Shape};
+    use aoc_utils::parse::{
+        ParseReport, expected, grid, label, lines, parse_all, space_separated, unsigned,
+    };
+    use winnow::{
+        ModalResult, Parser,
+        ascii::line_ending,
+        combinator::{alt, repeat, separated_pair, terminated},
+        error::{ContextError, ErrMode},
+    };
+
+    fn shape(input: &mut &str) -> ModalResult<Shape> {
+        let (_, _, rows): (usize, _, Vec<Vec<bool>>) = (
+            unsigned,
+            (':', line_ending),
+            grid(alt(('#'.value(true), '.'.value(false))))
+                .verify(|rows: &Vec<Vec<bool>>| rows.iter().flatten().any(|&filled| filled))
+                .context(expected("a shape with at least one `#`")),
+        )
+            .context(label("present shape"))
+            .parse_next(input)?;
+
+        Ok(Shape::new(rows.iter().enumerate().flat_map(|(r, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, filled)| **filled)
+                .map(move |(c, _)| (r, c))
+        })))
+    }
+
+    /// The widest and tallest region, beyond which packing is hopeless.
+    const MAX_SIDE: usize = 100;
+
+    /// A region with a count for each of the `shapes`.
+    fn region<'i>(shapes: usize) -> impl Parser<&'i str, Region, ErrMode<ContextError>> {
+        separated_pair(
+            separated_pair(unsigned, 'x', unsigned)
+                .verify(|&(width, height): &(usize, usize)| width.max(height) <= MAX_SIDE)
+                .context(expected("a region at most 100 wide and tall")),
+            ": ",
+            space_separated(unsigned)
+                .verify(move |counts: &Vec<usize>| counts.len() == shapes)
+                .context(expected("one count per shape")),
+        )
+        .map(|((width, height), counts)| Region {
+            width,
+            height,
+            counts,
+        })
+        .context(label("region"))
+    }
+
+    fn parser(input: &mut &str) -> ModalResult<(Vec<Shape>, Vec<Region>)> {
+        let shapes: Vec<Shape> =
+            repeat(1.., terminated(shape, (line_ending, line_ending))).parse_next(input)?;
+        let regions = lines(region(shapes.len())).parse_next(input)?;
+        Ok((shapes, regions))
+    }
+
+    pub fn parse_input(input: &str) -> Result<(Vec<Shape>, Vec<Region>), ParseReport> {
+        parse_all(parser, input)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_parse_input() {
+            let input = "0:
+##
+#.
+
+3x4: 2
+";
+            let (shapes, regions) = parse_input(input).unwrap();
+            assert_eq!(shapes, vec![Shape::new([(0, 0), (0, 1), (1, 0)])]);
+            assert_eq!(
+                regions,
+                vec![Region {
+                    width: 3,
+                    height: 4,
+                    counts: vec![2],
+                }]
+            );
+        }
+
+        #[test]
+        fn test_invalid_input() {
+            let report = parse_input("0:\n..\n..\n\n3x4: 2\n").unwrap_err();
+            assert_eq!((report.line, report.column), (2, 1));
+            assert_eq!(report.expected, vec!["a shape with at least one `#`"]);
+
+            let report = parse_input("0:\n#.\n\n3x4: 2 1\n").unwrap_err();
+            assert_eq!((report.line, report.column), (4, 6));
+            assert_eq!(report.expected, vec!["one count per shape"]);
+
+            let report = parse_input("0:\n#.\n\n1099511627776x1099511627776: 1\n").unwrap_err();
+            assert_eq!((report.line, report.column), (4, 1));
+            assert_eq!(report.expected, vec!["a region at most 100 wide and tall"]);
+            assert!(parse_input("0:\n#.\n\n100x100: 1\n").is_ok());
+        }
+    }
 }
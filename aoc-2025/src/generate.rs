@@ -0,0 +1,295 @@
+//! Random puzzle inputs for stress testing and benchmarking.
+//!
+//! Each generator writes an input in its day's format, sized by roughly how
+//! many lines or items the day has, and valid by construction: polygons are
+//! simple and rectilinear, every manual can be solved and the device graph is
+//! acyclic. The same seed always gives the same input.
+
+use std::collections::BTreeSet;
+use std::fmt::{self, Write};
+
+use aoc_utils::cli::Generate;
+use aoc_utils::rng::Rng;
+
+pub const YEAR: u32 = 2025;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GenerateError {
+    Year(u32),
+    Day(usize),
+}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Year(year) => write!(f, "no input generators for {}, only {}", year, YEAR),
+            Self::Day(day) => write!(f, "no input generator for day {}", day),
+        }
+    }
+}
+
+impl std::error::Error for GenerateError {}
+
+/// The input requested by `aoc gen`.
+pub fn generate(request: &Generate) -> Result<String, GenerateError> {
+    if request.year != YEAR {
+        return Err(GenerateError::Year(request.year));
+    }
+    let (generator, default_size): (fn(&mut Rng, usize) -> String, usize) = match request.day {
+        1 => (rotations, 4000),
+        2 => (id_ranges, 35),
+        3 => (battery_banks, 200),
+        4 => (paper_rolls, 135 * 135),
+        8 => (junctions, 1000),
+        9 => (polygon, 500),
+        10 => (manuals, 170),
+        11 => (devices, 600),
+        day => return Err(GenerateError::Day(day)),
+    };
+    let mut rng = Rng::new(request.seed);
+    Ok(generator(
+        &mut rng,
+        request.size.unwrap_or(default_size).max(1),
+    ))
+}
+
+/// Day 1: `size` rotations like `L68`.
+fn rotations(rng: &mut Rng, size: usize) -> String {
+    let mut out = String::new();
+    for _ in 0..size {
+        let direction = if rng.chance(0.5) { 'L' } else { 'R' };
+        writeln!(out, "{}{}", direction, rng.range(1..=999)).unwrap();
+    }
+    out
+}
+
+/// Day 2: `size` disjoint, increasing id ranges on one line.
+fn id_ranges(rng: &mut Rng, size: usize) -> String {
+    let gap = 10_000_000_000 / size as u64;
+    let mut next = 1;
+    let mut ranges = Vec::with_capacity(size);
+    for _ in 0..size {
+        let start = next + rng.below(gap / 2 + 1);
+        let end = start + rng.below((gap / 2).min(1_000_000) + 1);
+        ranges.push(format!("{}-{}", start, end));
+        next = end + 1;
+    }
+    ranges.join(",") + "\n"
+}
+
+/// Day 3: `size` banks of 100 joltage digits.
+fn battery_banks(rng: &mut Rng, size: usize) -> String {
+    let mut out = String::new();
+    for _ in 0..size {
+        out.extend((0..100).map(|_| char::from(b'1' + rng.below(9) as u8)));
+        out.push('\n');
+    }
+    out
+}
+
+/// Day 4: a square grid of about `size` cells with about two thirds of it
+/// paper rolls.
+fn paper_rolls(rng: &mut Rng, size: usize) -> String {
+    let side = size.isqrt().max(1);
+    let mut out = String::new();
+    for _ in 0..side {
+        out.extend((0..side).map(|_| if rng.chance(0.65) { '@' } else { '.' }));
+        out.push('\n');
+    }
+    out
+}
+
+/// Day 8: `size` junction boxes in a 100000 wide cube.
+fn junctions(rng: &mut Rng, size: usize) -> String {
+    let mut out = String::new();
+    for _ in 0..size {
+        let [x, y, z] = [(); 3].map(|_| rng.below(100_000));
+        writeln!(out, "{},{},{}", x, y, z).unwrap();
+    }
+    out
+}
+
+/// Day 9: the corners of a simple rectilinear polygon with about `size`
+/// corners.
+///
+/// The polygon is a row of columns, each spanning `bottom..=top` between two
+/// x coordinates, where neighbouring columns overlap vertically and differ in
+/// both their top and bottom so that no corner is straight. The outline runs
+/// along the tops from left to right and back along the bottoms.
+fn polygon(rng: &mut Rng, size: usize) -> String {
+    let columns = (size / 4).max(1);
+    let width = 100_000.max(4 * columns as u64);
+    let height = 100_000;
+
+    let mut xs = BTreeSet::new();
+    while xs.len() <= columns {
+        xs.insert(rng.range(1..=width));
+    }
+    let xs: Vec<_> = xs.into_iter().collect();
+
+    let bottom = rng.range(1..=height / 2);
+    let mut spans = vec![(bottom, rng.range(bottom + 1..=height))];
+    while spans.len() < columns {
+        let (prev_bottom, prev_top) = spans[spans.len() - 1];
+        let top = rng.range(prev_bottom + 1..=height);
+        let bottom = rng.range(1..=(top.min(prev_top) - 1).min(height / 2));
+        if top != prev_top && bottom != prev_bottom {
+            spans.push((bottom, top));
+        }
+    }
+
+    let mut out = String::new();
+    for (i, &(_, top)) in spans.iter().enumerate() {
+        writeln!(out, "{},{}\n{},{}", xs[i], top, xs[i + 1], top).unwrap();
+    }
+    for (i, &(bottom, _)) in spans.iter().enumerate().rev() {
+        writeln!(out, "{},{}\n{},{}", xs[i + 1], bottom, xs[i], bottom).unwrap();
+    }
+    out
+}
+
+/// Day 10: `size` manuals whose lights and joltages are reached by pressing
+/// a random selection of their buttons.
+fn manuals(rng: &mut Rng, size: usize) -> String {
+    let mut out = String::new();
+    for _ in 0..size {
+        let lights = rng.range(3..=10) as usize;
+        let buttons: Vec<Vec<usize>> = (0..rng.range(lights as u64 - 1..=lights as u64 + 3))
+            .map(|_| {
+                let wiring: Vec<_> = (0..lights).filter(|_| rng.chance(0.4)).collect();
+                if wiring.is_empty() {
+                    vec![rng.index(lights)]
+                } else {
+                    wiring
+                }
+            })
+            .collect();
+
+        let mut on = vec![false; lights];
+        let mut joltage = vec![0; lights];
+        for button in &buttons {
+            let presses = rng.below(20);
+            for &light in button {
+                on[light] ^= presses % 2 == 1;
+                joltage[light] += presses;
+            }
+        }
+
+        let on: String = on.iter().map(|&on| if on { '#' } else { '.' }).collect();
+        write!(out, "[{}]", on).unwrap();
+        for button in &buttons {
+            let wiring: Vec<_> = button.iter().map(usize::to_string).collect();
+            write!(out, " ({})", wiring.join(",")).unwrap();
+        }
+        let joltage: Vec<_> = joltage.iter().map(u64::to_string).collect();
+        writeln!(out, " {{{}}}", joltage.join(",")).unwrap();
+    }
+    out
+}
+
+/// Day 11: `size` devices of an acyclic graph from `svr` to `out`, with
+/// `you`, `fft` and `dac` spread along the way.
+///
+/// The devices are placed in a fixed order and only output to devices a
+/// bounded distance ahead. Outputs that would skip one of the named devices
+/// are often redirected to it, so that many paths pass through them. There
+/// are only `26^3` three letter names.
+fn devices(rng: &mut Rng, size: usize) -> String {
+    let size = size.clamp(5, 26 * 26 * 26);
+    let mut names: Vec<String> = (0..)
+        .map(|i: usize| {
+            let letter = |d: usize| char::from(b'a' + (i / d % 26) as u8);
+            [letter(26 * 26), letter(26), letter(1)]
+                .iter()
+                .collect::<String>()
+        })
+        .filter(|name| !["svr", "you", "fft", "dac", "out"].contains(&name.as_str()))
+        .take(size - 5)
+        .collect();
+    rng.shuffle(&mut names);
+    names.insert(0, "svr".to_owned());
+    names.insert(size / 10 + 1, "you".to_owned());
+    names.insert(size / 3, "fft".to_owned());
+    names.insert(2 * size / 3, "dac".to_owned());
+    names.push("out".to_owned());
+
+    let waypoints: Vec<_> = ["you", "fft", "dac"]
+        .map(|name| names.iter().position(|n| n == name).unwrap())
+        .into();
+    let reach = (size as u64 / 8).max(2);
+    let mut lines: Vec<_> = (0..size - 1)
+        .map(|i| {
+            let mut outputs: Vec<_> = (0..rng.range(1..=3))
+                .map(|_| {
+                    let output = (i + 1 + rng.below(reach) as usize).min(size - 1);
+                    match waypoints.iter().find(|&&w| i < w && w < output) {
+                        Some(&waypoint) if rng.chance(0.3) => waypoint,
+                        _ => output,
+                    }
+                })
+                .collect();
+            outputs.sort_unstable();
+            outputs.dedup();
+            let outputs: Vec<_> = outputs.iter().map(|&o| names[o].as_str()).collect();
+            format!("{}: {}\n", names[i], outputs.join(" "))
+        })
+        .collect();
+    rng.shuffle(&mut lines);
+    lines.concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{day01, day02, day03, day04, day08, day09, day10, day11};
+
+    fn generate_day(day: usize, size: usize, seed: u64) -> String {
+        generate(&Generate {
+            year: YEAR,
+            day,
+            size: Some(size),
+            seed,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn seeded() {
+        for day in [1, 2, 3, 4, 8, 9, 10, 11] {
+            assert_eq!(generate_day(day, 50, 1), generate_day(day, 50, 1));
+            assert_ne!(generate_day(day, 50, 1), generate_day(day, 50, 2));
+        }
+        let request = Generate {
+            year: 2024,
+            day: 1,
+            size: None,
+            seed: 0,
+        };
+        assert_eq!(generate(&request), Err(GenerateError::Year(2024)));
+    }
+
+    #[test]
+    fn generated_inputs_are_solvable() {
+        for seed in 0..5 {
+            let input = day01::process(&generate_day(1, 100, seed)).unwrap();
+            day01::part2(&input);
+            day02::process(&generate_day(2, 10, seed)).unwrap();
+            let input = day03::process(&generate_day(3, 20, seed)).unwrap();
+            day03::part2(&input);
+            let input = day04::process(&generate_day(4, 400, seed), &Default::default()).unwrap();
+            day04::part2(&input);
+            let input =
+                day08::process(&generate_day(8, 50, seed), &day08::Params { limit: 10 }).unwrap();
+            day08::part2(&input);
+            let input = day09::process(&generate_day(9, 40, seed), &Default::default()).unwrap();
+            assert_eq!(input.tiles.len(), 40);
+            day09::part2(&input);
+            let input = day10::process(&generate_day(10, 5, seed)).unwrap();
+            day10::part1(&input).unwrap();
+            day10::part2(&input).unwrap();
+            let input = day11::process(&generate_day(11, 50, seed)).unwrap();
+            day11::part1(&input);
+            day11::part2(&input);
+        }
+    }
+}
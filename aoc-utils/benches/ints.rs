@@ -0,0 +1,67 @@
+//! Compares `ints::lines_of` with the equivalent `winnow` parsers on inputs
+//! shaped like day08 (junction triples) and day09 (tile pairs).
+
+use std::fmt::Write;
+use std::hint::black_box;
+
+use aoc_utils::ints::{ints, lines_of};
+use aoc_utils::parse::{lines, parse_all, unsigned};
+use criterion::{Criterion, criterion_group, criterion_main};
+use winnow::{Parser, combinator::preceded};
+
+/// `rows` lines of `cols` comma separated numbers from a fixed LCG.
+fn input(rows: usize, cols: usize) -> String {
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut out = String::new();
+    for _ in 0..rows {
+        for c in 0..cols {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let sep = if c == 0 { "" } else { "," };
+            write!(out, "{}{}", sep, (state >> 33) % 100_000).unwrap();
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn triples(c: &mut Criterion) {
+    let input = input(1000, 3);
+    let mut group = c.benchmark_group("triples");
+    group.bench_function("winnow", |b| {
+        b.iter(|| {
+            let triple = (
+                unsigned::<u32>,
+                preceded(',', unsigned::<u32>),
+                preceded(',', unsigned::<u32>),
+            );
+            parse_all(lines(triple.map(|(x, y, z)| [x, y, z])), black_box(&input)).unwrap()
+        })
+    });
+    group.bench_function("lines_of", |b| {
+        b.iter(|| lines_of::<[u32; 3]>(black_box(&input)).unwrap())
+    });
+    group.bench_function("ints", |b| {
+        b.iter(|| ints::<u32>(black_box(&input)).sum::<u32>())
+    });
+    group.finish();
+}
+
+fn pairs(c: &mut Criterion) {
+    let input = input(500, 2);
+    let mut group = c.benchmark_group("pairs");
+    group.bench_function("winnow", |b| {
+        b.iter(|| {
+            let pair = (unsigned::<u64>, preceded(',', unsigned::<u64>));
+            parse_all(lines(pair.map(|(x, y)| [x, y])), black_box(&input)).unwrap()
+        })
+    });
+    group.bench_function("lines_of", |b| {
+        b.iter(|| lines_of::<[u64; 2]>(black_box(&input)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, triples, pairs);
+criterion_main!(benches);
@@ -0,0 +1,54 @@
+//! What the parts of a day return.
+
+use std::fmt::Display;
+
+use crate::bigint::BigUint;
+
+/// An answer as the runner prints it.
+///
+/// Parts return the answer itself, or a `Result` when some well-formed
+/// inputs have no answer, whose error is printed in place of the answer.
+pub trait Answer {
+    fn to_answer(&self) -> String;
+}
+
+macro_rules! impl_answer {
+    ($($ty:ty),*) => {
+        $(
+            impl Answer for $ty {
+                fn to_answer(&self) -> String {
+                    self.to_string()
+                }
+            }
+        )*
+    };
+}
+
+impl_answer!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, String, BigUint
+);
+
+impl<T: Answer, E: Display> Answer for Result<T, E> {
+    fn to_answer(&self) -> String {
+        match self {
+            Ok(answer) => answer.to_answer(),
+            Err(err) => format!("failed: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers() {
+        assert_eq!(42u64.to_answer(), "42");
+        assert_eq!(BigUint::from(7u64).to_answer(), "7");
+        assert_eq!(Ok::<i32, String>(-3).to_answer(), "-3");
+        assert_eq!(
+            Err::<i32, _>("no solution").to_answer(),
+            "failed: no solution"
+        );
+    }
+}
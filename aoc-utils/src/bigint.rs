@@ -0,0 +1,241 @@
+//! Unsigned integers of any size, for counts that outgrow `u128`.
+//!
+//! Counting paths through a graph doubles at every branch, so deep inputs
+//! overflow any fixed width. Only what counting needs is implemented: adding,
+//! comparing, and reading and printing in decimal.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign};
+
+/// An unsigned integer stored as `u64` limbs, least significant first,
+/// without trailing zero limbs.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    limbs: Vec<u64>,
+}
+
+impl BigUint {
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// The number of bits needed to write the number, `0` for zero.
+    pub fn bits(&self) -> u64 {
+        match self.limbs.last() {
+            Some(last) => {
+                64 * (self.limbs.len() as u64 - 1) + (u64::BITS - last.leading_zeros()) as u64
+            }
+            None => 0,
+        }
+    }
+
+    /// The value as a `u128`, if it fits.
+    pub fn to_u128(&self) -> Option<u128> {
+        match self.limbs[..] {
+            [] => Some(0),
+            [low] => Some(low as u128),
+            [low, high] => Some((high as u128) << 64 | low as u128),
+            _ => None,
+        }
+    }
+
+    /// The number with the decimal `digits`, most significant first.
+    ///
+    /// # Panics
+    ///
+    /// If a digit is 10 or more.
+    pub fn from_decimal_digits(digits: impl IntoIterator<Item = u8>) -> Self {
+        let mut n = Self::zero();
+        for digit in digits {
+            assert!(digit < 10, "not a decimal digit: {}", digit);
+            n.mul_add_small(10, digit as u64);
+        }
+        n
+    }
+
+    /// Multiplies in place by `factor` and adds `addend`.
+    fn mul_add_small(&mut self, factor: u64, addend: u64) {
+        let mut carry = addend as u128;
+        for limb in self.limbs.iter_mut() {
+            let acc = *limb as u128 * factor as u128 + carry;
+            *limb = acc as u64;
+            carry = acc >> 64;
+        }
+        if carry > 0 {
+            self.limbs.push(carry as u64);
+        }
+        self.trim();
+    }
+
+    /// Divides in place by `divisor`, returning the remainder.
+    fn div_rem_small(&mut self, divisor: u64) -> u64 {
+        let mut rem = 0u128;
+        for limb in self.limbs.iter_mut().rev() {
+            let acc = (rem << 64) | *limb as u128;
+            *limb = (acc / divisor as u128) as u64;
+            rem = acc % divisor as u128;
+        }
+        self.trim();
+        rem as u64
+    }
+
+    fn trim(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(n: u64) -> Self {
+        Self::from(n as u128)
+    }
+}
+
+impl From<u128> for BigUint {
+    fn from(n: u128) -> Self {
+        let mut big = Self {
+            limbs: vec![n as u64, (n >> 64) as u64],
+        };
+        big.trim();
+        big
+    }
+}
+
+impl AddAssign<&BigUint> for BigUint {
+    fn add_assign(&mut self, other: &BigUint) {
+        if self.limbs.len() < other.limbs.len() {
+            self.limbs.resize(other.limbs.len(), 0);
+        }
+        let mut carry = false;
+        for (i, limb) in self.limbs.iter_mut().enumerate() {
+            let (sum, overflow) = limb.overflowing_add(other.limbs.get(i).copied().unwrap_or(0));
+            let (sum, carry_overflow) = sum.overflowing_add(carry as u64);
+            *limb = sum;
+            carry = overflow || carry_overflow;
+            if !carry && i >= other.limbs.len() {
+                break;
+            }
+        }
+        if carry {
+            self.limbs.push(1);
+        }
+    }
+}
+
+impl AddAssign for BigUint {
+    fn add_assign(&mut self, other: BigUint) {
+        *self += &other;
+    }
+}
+
+impl Add<&BigUint> for BigUint {
+    type Output = BigUint;
+
+    fn add(mut self, other: &BigUint) -> BigUint {
+        self += other;
+        self
+    }
+}
+
+impl Add for BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: BigUint) -> BigUint {
+        self + &other
+    }
+}
+
+impl<'a> Sum<&'a BigUint> for BigUint {
+    fn sum<I: Iterator<Item = &'a BigUint>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |acc, n| acc + n)
+    }
+}
+
+impl Sum for BigUint {
+    fn sum<I: Iterator<Item = BigUint>>(iter: I) -> Self {
+        iter.fold(Self::zero(), Add::add)
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Peel off 19 decimal digits at a time, the most that fit in a limb.
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+        let mut rest = self.clone();
+        let mut chunks = vec![];
+        while !rest.is_zero() {
+            chunks.push(rest.div_rem_small(CHUNK));
+        }
+        let mut digits = chunks.pop().unwrap_or(0).to_string();
+        for chunk in chunks.iter().rev() {
+            digits.push_str(&format!("{:019}", chunk));
+        }
+        f.pad_integral(true, "", &digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_and_display() {
+        let max = BigUint::from(u128::MAX);
+        assert_eq!(max.to_string(), u128::MAX.to_string());
+        assert_eq!(max.to_u128(), Some(u128::MAX));
+
+        let doubled = max.clone() + &max;
+        assert_eq!(
+            doubled.to_string(),
+            "680564733841876926926749214863536422910"
+        );
+        assert_eq!(doubled.bits(), 129);
+        assert_eq!(doubled.to_u128(), None);
+        assert!(doubled > max && max > BigUint::from(1u64));
+
+        let sum: BigUint = [BigUint::from(7u64), BigUint::from(u64::MAX)].iter().sum();
+        assert_eq!(sum, BigUint::from(u64::MAX as u128 + 7));
+        assert_eq!(BigUint::zero().to_string(), "0");
+        assert_eq!(format!("{:>3}", BigUint::from(5u64)), "  5");
+    }
+
+    #[test]
+    fn from_decimal_digits() {
+        assert_eq!(BigUint::from_decimal_digits([]), BigUint::zero());
+        assert_eq!(
+            BigUint::from_decimal_digits([0, 0, 1, 3]),
+            BigUint::from(13u64)
+        );
+
+        let digits = u128::MAX.to_string().into_bytes();
+        let max = BigUint::from_decimal_digits(digits.iter().map(|d| d - b'0'));
+        assert_eq!(max, BigUint::from(u128::MAX));
+
+        let nines = BigUint::from_decimal_digits(std::iter::repeat_n(9, 60));
+        assert_eq!(nines.to_string(), "9".repeat(60));
+        let sum = nines.clone() + nines + BigUint::from(2u64);
+        assert_eq!(sum.to_string(), format!("2{}", "0".repeat(60)));
+    }
+}
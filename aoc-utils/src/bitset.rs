@@ -0,0 +1,385 @@
+//! Fixed-length bit sets backed by `u64` words.
+//!
+//! The storage is either a `Vec<u64>`, which can be resized, or a `[u64; N]`
+//! array for sets that live on the stack. Bits past `len` are always zero, so
+//! equality and population counts can work on whole words.
+
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign};
+use std::str::FromStr;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Storage for the words of a [`BitSet`].
+pub trait Words: AsRef<[u64]> + AsMut<[u64]> + Clone {
+    /// The most words the storage can hold, if bounded.
+    const CAPACITY: Option<usize>;
+
+    /// Zeroed storage holding at least `words` words.
+    fn zeroed(words: usize) -> Self;
+}
+
+impl Words for Vec<u64> {
+    const CAPACITY: Option<usize> = None;
+
+    fn zeroed(words: usize) -> Self {
+        vec![0; words]
+    }
+}
+
+impl<const N: usize> Words for [u64; N] {
+    const CAPACITY: Option<usize> = Some(N);
+
+    fn zeroed(words: usize) -> Self {
+        assert!(words <= N, "{} words do not fit in [u64; {}]", words, N);
+        [0; N]
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct BitSet<W = Vec<u64>> {
+    words: W,
+    len: usize,
+}
+
+/// A bit set with inline storage for up to `64 * N` bits.
+pub type FixedBitSet<const N: usize> = BitSet<[u64; N]>;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseBitSetError {
+    MissingBrackets,
+    InvalidChar(char),
+    TooLong(usize),
+}
+
+impl fmt::Display for ParseBitSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingBrackets => write!(f, "expected bits enclosed in '[' and ']'"),
+            Self::InvalidChar(c) => write!(f, "expected '.' or '#', found {:?}", c),
+            Self::TooLong(len) => write!(f, "{} bits do not fit in the set", len),
+        }
+    }
+}
+
+impl std::error::Error for ParseBitSetError {}
+
+#[inline]
+fn split(index: usize) -> (usize, u64) {
+    (index / WORD_BITS, 1 << (index % WORD_BITS))
+}
+
+impl<W: Words> BitSet<W> {
+    /// An empty set over `len` bits.
+    pub fn new(len: usize) -> Self {
+        Self {
+            words: W::zeroed(len.div_ceil(WORD_BITS)),
+            len,
+        }
+    }
+
+    /// A set over `len` bits with the given indices set.
+    ///
+    /// Panics if an index is out of range, see [`BitSet::try_from_indices`].
+    pub fn from_indices(len: usize, indices: impl IntoIterator<Item = usize>) -> Self {
+        Self::try_from_indices(len, indices).expect("index out of range")
+    }
+
+    /// Like [`BitSet::from_indices`], returning the first out of range index
+    /// as the error.
+    pub fn try_from_indices(
+        len: usize,
+        indices: impl IntoIterator<Item = usize>,
+    ) -> Result<Self, usize> {
+        let mut set = Self::new(len);
+        for i in indices {
+            if i >= len {
+                return Err(i);
+            }
+            set.insert(i);
+        }
+        Ok(set)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    fn words(&self) -> &[u64] {
+        &self.words.as_ref()[..self.len.div_ceil(WORD_BITS)]
+    }
+
+    #[inline]
+    fn words_mut(&mut self) -> &mut [u64] {
+        let n = self.len.div_ceil(WORD_BITS);
+        &mut self.words.as_mut()[..n]
+    }
+
+    #[inline]
+    pub fn contains(&self, index: usize) -> bool {
+        assert!(index < self.len, "index {} out of range", index);
+        let (word, mask) = split(index);
+        self.words()[word] & mask != 0
+    }
+
+    #[inline]
+    pub fn set(&mut self, index: usize, value: bool) {
+        if value {
+            self.insert(index);
+        } else {
+            self.remove(index);
+        }
+    }
+
+    #[inline]
+    pub fn insert(&mut self, index: usize) {
+        assert!(index < self.len, "index {} out of range", index);
+        let (word, mask) = split(index);
+        self.words_mut()[word] |= mask;
+    }
+
+    #[inline]
+    pub fn remove(&mut self, index: usize) {
+        assert!(index < self.len, "index {} out of range", index);
+        let (word, mask) = split(index);
+        self.words_mut()[word] &= !mask;
+    }
+
+    #[inline]
+    pub fn toggle(&mut self, index: usize) {
+        assert!(index < self.len, "index {} out of range", index);
+        let (word, mask) = split(index);
+        self.words_mut()[word] ^= mask;
+    }
+
+    pub fn clear(&mut self) {
+        self.words_mut().fill(0);
+    }
+
+    #[inline]
+    pub fn count_ones(&self) -> usize {
+        self.words().iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    #[inline]
+    pub fn none(&self) -> bool {
+        self.words().iter().all(|&w| w == 0)
+    }
+
+    /// Whether no bit is set in both `self` and `other`.
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        assert_eq!(self.len, other.len, "bit sets differ in length");
+        self.words()
+            .iter()
+            .zip(other.words())
+            .all(|(l, r)| l & r == 0)
+    }
+
+    /// Whether every bit set in `self` is also set in `other`.
+    pub fn is_subset(&self, other: &Self) -> bool {
+        assert_eq!(self.len, other.len, "bit sets differ in length");
+        self.words()
+            .iter()
+            .zip(other.words())
+            .all(|(l, r)| l & !r == 0)
+    }
+
+    /// Indices of the set bits in increasing order.
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words().iter().enumerate().flat_map(|(i, &word)| {
+            let mut word = word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(i * WORD_BITS + bit)
+            })
+        })
+    }
+
+    /// Every bit in order, set or not.
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(|i| self.contains(i))
+    }
+
+    fn zip_assign(&mut self, rhs: &Self, op: impl Fn(&mut u64, u64)) {
+        assert_eq!(self.len, rhs.len, "bit sets differ in length");
+        for (l, &r) in self.words_mut().iter_mut().zip(rhs.words()) {
+            op(l, r);
+        }
+    }
+}
+
+impl BitSet {
+    /// Changes the length to `len`, clearing any dropped bits.
+    pub fn resize(&mut self, len: usize) {
+        self.words.resize(len.div_ceil(WORD_BITS), 0);
+        if len < self.len && !len.is_multiple_of(WORD_BITS) {
+            let last = self.words.len() - 1;
+            self.words[last] &= (1 << (len % WORD_BITS)) - 1;
+        }
+        self.len = len;
+    }
+
+    /// Appends a bit, growing the set by one.
+    pub fn push(&mut self, value: bool) {
+        self.resize(self.len + 1);
+        self.set(self.len - 1, value);
+    }
+}
+
+macro_rules! impl_bit_op {
+    ($trait:ident, $method:ident, $assign_trait:ident, $assign_method:ident, $op:tt) => {
+        impl<W: Words> $assign_trait<&BitSet<W>> for BitSet<W> {
+            fn $assign_method(&mut self, rhs: &BitSet<W>) {
+                self.zip_assign(rhs, |l, r| *l $op r);
+            }
+        }
+
+        impl<W: Words> $assign_trait for BitSet<W> {
+            fn $assign_method(&mut self, rhs: BitSet<W>) {
+                self.zip_assign(&rhs, |l, r| *l $op r);
+            }
+        }
+
+        impl<W: Words> $trait<&BitSet<W>> for &BitSet<W> {
+            type Output = BitSet<W>;
+
+            fn $method(self, rhs: &BitSet<W>) -> Self::Output {
+                let mut out = self.clone();
+                out.zip_assign(rhs, |l, r| *l $op r);
+                out
+            }
+        }
+
+        impl<W: Words> $trait for BitSet<W> {
+            type Output = BitSet<W>;
+
+            fn $method(mut self, rhs: BitSet<W>) -> Self::Output {
+                self.zip_assign(&rhs, |l, r| *l $op r);
+                self
+            }
+        }
+    };
+}
+
+impl_bit_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, ^=);
+impl_bit_op!(BitAnd, bitand, BitAndAssign, bitand_assign, &=);
+impl_bit_op!(BitOr, bitor, BitOrAssign, bitor_assign, |=);
+
+impl<W: Words> fmt::Display for BitSet<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        for b in self.iter() {
+            write!(f, "{}", if b { '#' } else { '.' })?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl<W: Words> fmt::Debug for BitSet<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BitSet({})", self)
+    }
+}
+
+impl<W: Words> FromStr for BitSet<W> {
+    type Err = ParseBitSetError;
+
+    /// Parses the `[.##.]` notation, where `#` is a set bit.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bits = s
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .ok_or(ParseBitSetError::MissingBrackets)?;
+
+        let len = bits.chars().count();
+        if W::CAPACITY.is_some_and(|words| len > words * WORD_BITS) {
+            return Err(ParseBitSetError::TooLong(len));
+        }
+        let mut set = Self::new(len);
+        for (i, c) in bits.chars().enumerate() {
+            match c {
+                '#' => set.insert(i),
+                '.' => {}
+                c => return Err(ParseBitSetError::InvalidChar(c)),
+            }
+        }
+        Ok(set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_display() {
+        let set: BitSet = "[.##.]".parse().unwrap();
+        assert_eq!(set.len(), 4);
+        assert_eq!(set.iter_ones().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(set.to_string(), "[.##.]");
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            ".##.".parse::<BitSet>(),
+            Err(ParseBitSetError::MissingBrackets)
+        );
+        assert_eq!(
+            "[.#x]".parse::<BitSet>(),
+            Err(ParseBitSetError::InvalidChar('x'))
+        );
+        let long = format!("[{}]", "#".repeat(65));
+        assert_eq!(
+            long.parse::<FixedBitSet<1>>(),
+            Err(ParseBitSetError::TooLong(65))
+        );
+    }
+
+    #[test]
+    fn wide_operations() {
+        let a: BitSet = BitSet::from_indices(130, [0, 64, 129]);
+        let b: BitSet = BitSet::from_indices(130, [64, 100]);
+        assert_eq!((&a ^ &b).iter_ones().collect::<Vec<_>>(), vec![0, 100, 129]);
+        assert_eq!((&a & &b).iter_ones().collect::<Vec<_>>(), vec![64]);
+        assert_eq!((&a | &b).count_ones(), 4);
+        assert!(!a.is_disjoint(&b));
+        assert!((&a & &b).is_subset(&b));
+        assert!(!a.is_subset(&b));
+    }
+
+    #[test]
+    fn fixed_storage() {
+        let mut set = FixedBitSet::<2>::new(100);
+        set.insert(99);
+        set.toggle(3);
+        assert_eq!(set.iter_ones().collect::<Vec<_>>(), vec![3, 99]);
+        assert_eq!(set, FixedBitSet::<2>::from_indices(100, [3, 99]));
+    }
+
+    #[test]
+    fn resize_clears_dropped_bits() {
+        let mut set: BitSet = BitSet::from_indices(10, [2, 8]);
+        set.resize(5);
+        set.resize(10);
+        assert_eq!(set.iter_ones().collect::<Vec<_>>(), vec![2]);
+        set.push(true);
+        assert_eq!(set.to_string(), "[..#.......#]");
+    }
+
+    #[test]
+    fn out_of_range_index() {
+        assert_eq!(BitSet::<Vec<u64>>::try_from_indices(4, [1, 4]), Err(4));
+    }
+}
@@ -0,0 +1,312 @@
+//! Command line arguments of the year binaries.
+
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::visualize::Format;
+
+pub const USAGE: &str =
+    "usage: aoc [DAY]... [--input FILE] [--param [DAY.]NAME=VALUE]... [--visualize FORMAT]
+       aoc gen YEAR DAY [--size N] [--seed N]
+
+  DAY                 only run these days, e.g. `8` or `day08`
+  --input FILE        read the puzzle input from FILE instead of the cache
+  --param [DAY.]NAME=VALUE
+                      override a puzzle parameter of DAY, or of the days that
+                      run and have one called NAME
+  --visualize FORMAT  draw the steps of the days that support it, as ansi
+                      frames in the terminal or ppm, png or gif files
+                      under visualize/DAY
+
+  gen YEAR DAY        print a random input for the day instead of running
+  --size N            roughly how many lines or items, the day's default if omitted
+  --seed N            seed of the generator, 0 if omitted";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Args {
+    /// Days to run, all when empty.
+    pub days: Vec<usize>,
+    pub input: Option<PathBuf>,
+    /// Parameter overrides in the order given.
+    pub params: Vec<Param>,
+    pub visualize: Option<Format>,
+    /// Set by `gen`, which prints an input instead of running the days.
+    pub generate: Option<Generate>,
+}
+
+/// A parameter override, for a single day if it names one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Param {
+    pub day: Option<usize>,
+    pub name: String,
+    pub value: String,
+}
+
+/// A request for a random puzzle input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Generate {
+    pub year: u32,
+    pub day: usize,
+    pub size: Option<usize>,
+    pub seed: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ArgsError {
+    MissingValue(&'static str),
+    InvalidValue(&'static str, String),
+    InvalidParam(String),
+    InvalidDay(String),
+    UnknownFlag(String),
+}
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingValue(flag) => write!(f, "{} needs a value", flag),
+            Self::InvalidValue(flag, value) => write!(f, "invalid {} `{}`", flag, value),
+            Self::InvalidParam(param) => write!(f, "expected NAME=VALUE, found `{}`", param),
+            Self::InvalidDay(day) => write!(f, "`{}` is not a day", day),
+            Self::UnknownFlag(flag) => write!(f, "unknown option `{}`", flag),
+        }
+    }
+}
+
+impl std::error::Error for ArgsError {}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, ArgsError> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter().peekable();
+        if args.next_if(|arg| arg == "gen").is_some() {
+            parsed.generate = Some(Generate::parse(args)?);
+            return Ok(parsed);
+        }
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--input" => {
+                    let path = args.next().ok_or(ArgsError::MissingValue("--input"))?;
+                    parsed.input = Some(path.into());
+                }
+                "--param" => {
+                    let param = args.next().ok_or(ArgsError::MissingValue("--param"))?;
+                    let (name, value) = param
+                        .split_once('=')
+                        .ok_or_else(|| ArgsError::InvalidParam(param.clone()))?;
+                    let (day, name) = match name.split_once('.') {
+                        Some((day, name)) => (Some(parse_day(day)?), name),
+                        None => (None, name),
+                    };
+                    parsed.params.push(Param {
+                        day,
+                        name: name.to_owned(),
+                        value: value.to_owned(),
+                    });
+                }
+                "--visualize" => {
+                    let format = args.next().ok_or(ArgsError::MissingValue("--visualize"))?;
+                    parsed.visualize = Some(
+                        format
+                            .parse()
+                            .map_err(|_| ArgsError::InvalidValue("--visualize", format))?,
+                    );
+                }
+                flag if flag.starts_with('-') => return Err(ArgsError::UnknownFlag(arg)),
+                day => parsed.days.push(parse_day(day)?),
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Parses the arguments of the process, exiting with the usage on error.
+    pub fn from_env() -> Self {
+        Self::parse(std::env::args().skip(1)).unwrap_or_else(|err| {
+            eprintln!("{}\n\n{}", err, USAGE);
+            std::process::exit(2);
+        })
+    }
+
+    /// Whether `day` was selected.
+    pub fn runs(&self, day: usize) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    /// Applies the parameter overrides for `day` to its `params`.
+    ///
+    /// Overrides without a day apply to every day that runs, except that when
+    /// several days run, those without a parameter of that name ignore it.
+    pub fn apply<P: crate::params::Params>(
+        &self,
+        day: usize,
+        params: &mut P,
+    ) -> Result<(), crate::params::ParamError> {
+        let several = self.days.len() != 1;
+        for param in &self.params {
+            let applies = match param.day {
+                Some(only) => only == day,
+                None => !several || P::NAMES.contains(&param.name.as_str()),
+            };
+            if applies {
+                params.set(&param.name, &param.value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses a day like `8` or `day08`.
+fn parse_day(day: &str) -> Result<usize, ArgsError> {
+    day.strip_prefix("day")
+        .unwrap_or(day)
+        .parse()
+        .map_err(|_| ArgsError::InvalidDay(day.to_owned()))
+}
+
+impl Generate {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, ArgsError> {
+        fn value<T: std::str::FromStr>(
+            name: &'static str,
+            value: Option<String>,
+        ) -> Result<T, ArgsError> {
+            let value = value.ok_or(ArgsError::MissingValue(name))?;
+            value
+                .parse()
+                .map_err(|_| ArgsError::InvalidValue(name, value))
+        }
+
+        let year = value("YEAR", args.next())?;
+        let day = parse_day(&args.next().ok_or(ArgsError::MissingValue("DAY"))?)?;
+        let mut generate = Self {
+            year,
+            day,
+            size: None,
+            seed: 0,
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--size" => generate.size = Some(value("--size", args.next())?),
+                "--seed" => generate.seed = value("--seed", args.next())?,
+                _ => return Err(ArgsError::UnknownFlag(arg)),
+            }
+        }
+        Ok(generate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, ArgsError> {
+        Args::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn days_input_and_params() {
+        let args = parse(&[
+            "day08",
+            "9",
+            "--input",
+            "ex.txt",
+            "--param",
+            "limit=10",
+            "--visualize",
+            "png",
+        ])
+        .unwrap();
+        assert_eq!(args.days, vec![8, 9]);
+        assert_eq!(args.visualize, Some(Format::Png));
+        assert_eq!(args.input, Some(PathBuf::from("ex.txt")));
+        let limit = Param {
+            day: None,
+            name: "limit".to_owned(),
+            value: "10".to_owned(),
+        };
+        assert_eq!(args.params, vec![limit]);
+        assert!(args.runs(8) && !args.runs(1));
+        assert!(parse(&[]).unwrap().runs(1));
+    }
+
+    #[test]
+    fn generate() {
+        let args = parse(&["gen", "2025", "8", "--size", "10000", "--seed", "1"]).unwrap();
+        let expected = Generate {
+            year: 2025,
+            day: 8,
+            size: Some(10000),
+            seed: 1,
+        };
+        assert_eq!(args.generate, Some(expected));
+        assert_eq!(
+            parse(&["gen", "2025", "day08"])
+                .unwrap()
+                .generate
+                .unwrap()
+                .seed,
+            0
+        );
+        assert_eq!(
+            parse(&["gen", "2025", "8", "--size", "big"]),
+            Err(ArgsError::InvalidValue("--size", "big".to_owned()))
+        );
+        assert_eq!(parse(&["gen", "2025"]), Err(ArgsError::MissingValue("DAY")));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse(&["--param", "limit"]),
+            Err(ArgsError::InvalidParam("limit".to_owned()))
+        );
+        assert_eq!(parse(&["--input"]), Err(ArgsError::MissingValue("--input")));
+        assert_eq!(
+            parse(&["--visualize", "svg"]),
+            Err(ArgsError::InvalidValue("--visualize", "svg".to_owned()))
+        );
+        assert_eq!(
+            parse(&["--fast"]),
+            Err(ArgsError::UnknownFlag("--fast".to_owned()))
+        );
+        assert_eq!(
+            parse(&["dayX"]),
+            Err(ArgsError::InvalidDay("dayX".to_owned()))
+        );
+    }
+
+    crate::params! {
+        struct Limit {
+            limit: usize = 1000,
+        }
+    }
+
+    #[test]
+    fn scoped_params() {
+        let args = parse(&["--param", "day08.limit=10", "--param", "8.limit=20"]).unwrap();
+        assert_eq!(args.params[0].day, Some(8));
+        assert_eq!(args.params[1].day, Some(8));
+        let mut params = Limit::default();
+        args.apply(8, &mut params).unwrap();
+        assert_eq!(params.limit, 20);
+        // Other days, with or without parameters, are left alone.
+        args.apply(4, &mut ()).unwrap();
+        assert!(
+            parse(&["--param", "day8.limt=1"])
+                .unwrap()
+                .apply(8, &mut params)
+                .is_err()
+        );
+        assert_eq!(
+            parse(&["--param", "dayX.limit=1"]),
+            Err(ArgsError::InvalidDay("dayX".to_owned()))
+        );
+
+        // Unscoped names only fail a day they are unknown to when it runs alone.
+        let all = parse(&["--param", "limit=10"]).unwrap();
+        all.apply(4, &mut ()).unwrap();
+        let mut params = Limit::default();
+        all.apply(8, &mut params).unwrap();
+        assert_eq!(params.limit, 10);
+        let alone = parse(&["4", "--param", "limit=10"]).unwrap();
+        assert!(alone.apply(4, &mut ()).is_err());
+    }
+}
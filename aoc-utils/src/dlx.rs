@@ -0,0 +1,495 @@
+//! Exact cover with Knuth's Algorithm X over dancing links.
+//!
+//! Every primary column must be covered exactly once, secondary columns at
+//! most once or up to their capacity. The search is iterative, so solutions can be pulled one at a
+//! time from [`ExactCover::solutions`] and the search stopped at any point.
+
+use std::ops::ControlFlow;
+
+const ROOT: usize = 0;
+
+/// An exact cover problem in dancing links form.
+///
+/// Columns `0..primary` are primary and `primary..primary + secondary` are
+/// secondary. Rows are numbered in the order they are added.
+#[derive(Clone, Debug)]
+pub struct ExactCover {
+    primary: usize,
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    /// Column header of every node, headers point at themselves.
+    top: Vec<usize>,
+    /// Row index of every node, unused for headers.
+    row: Vec<usize>,
+    /// Number of nodes in each column, indexed by header.
+    len: Vec<usize>,
+    /// How many more rows may cover each column, indexed by header, and `0`
+    /// once it is covered by a row.
+    capacity: Vec<usize>,
+    rows: usize,
+}
+
+impl ExactCover {
+    pub fn new(primary: usize, secondary: usize) -> Self {
+        let headers = 1 + primary + secondary;
+        let mut left: Vec<_> = (0..headers).collect();
+        let mut right: Vec<_> = (0..headers).collect();
+        for h in 0..=primary {
+            left[h] = if h == ROOT { primary } else { h - 1 };
+            right[h] = if h == primary { ROOT } else { h + 1 };
+        }
+
+        Self {
+            primary,
+            left,
+            right,
+            up: (0..headers).collect(),
+            down: (0..headers).collect(),
+            top: (0..headers).collect(),
+            row: vec![usize::MAX; headers],
+            len: vec![0; headers],
+            capacity: vec![1; headers],
+            rows: 0,
+        }
+    }
+
+    #[inline]
+    pub fn columns(&self) -> usize {
+        self.len.len() - 1
+    }
+
+    #[inline]
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Lets up to `capacity` rows cover a secondary column.
+    ///
+    /// Panics unless the column is secondary and `capacity` is positive.
+    pub fn set_capacity(&mut self, column: usize, capacity: usize) {
+        assert!(
+            (self.primary..self.columns()).contains(&column),
+            "column {} is not secondary",
+            column
+        );
+        assert!(capacity > 0, "capacity must be positive");
+        self.capacity[column + 1] = capacity;
+    }
+
+    /// Adds a row covering the given columns and returns its index.
+    ///
+    /// Panics if a column is out of range or repeated.
+    pub fn add_row(&mut self, columns: impl IntoIterator<Item = usize>) -> usize {
+        let row = self.rows;
+        let mut first: Option<usize> = None;
+        let mut seen = vec![];
+        for column in columns {
+            assert!(column < self.columns(), "column {} out of range", column);
+            assert!(!seen.contains(&column), "column {} repeated", column);
+            seen.push(column);
+
+            let header = column + 1;
+            let node = self.top.len();
+            self.top.push(header);
+            self.row.push(row);
+            self.len[header] += 1;
+
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            let above = self.up[header];
+            self.down[above] = node;
+            self.up[header] = node;
+
+            match first {
+                None => {
+                    self.left.push(node);
+                    self.right.push(node);
+                    first = Some(node);
+                }
+                Some(first) => {
+                    let last = self.left[first];
+                    self.left.push(last);
+                    self.right.push(first);
+                    self.right[last] = node;
+                    self.left[first] = node;
+                }
+            }
+        }
+        self.rows += 1;
+        row
+    }
+
+    fn cover(&mut self, header: usize) {
+        let (l, r) = (self.left[header], self.right[header]);
+        self.right[l] = r;
+        self.left[r] = l;
+
+        let mut i = self.down[header];
+        while i != header {
+            let mut j = self.right[i];
+            while j != i {
+                let (u, d) = (self.up[j], self.down[j]);
+                self.down[u] = d;
+                self.up[d] = u;
+                self.len[self.top[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    fn uncover(&mut self, header: usize) {
+        let mut i = self.up[header];
+        while i != header {
+            let mut j = self.left[i];
+            while j != i {
+                let (u, d) = (self.up[j], self.down[j]);
+                self.down[u] = j;
+                self.up[d] = j;
+                self.len[self.top[j]] += 1;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+
+        let (l, r) = (self.left[header], self.right[header]);
+        self.right[l] = header;
+        self.left[r] = header;
+    }
+
+    /// Takes one use of each other column of the row, covering those that
+    /// have none left.
+    fn cover_row(&mut self, node: usize) {
+        let mut j = self.right[node];
+        while j != node {
+            let header = self.top[j];
+            if self.capacity[header] > 1 {
+                self.capacity[header] -= 1;
+            } else {
+                self.capacity[header] = 0;
+                self.cover(header);
+            }
+            j = self.right[j];
+        }
+    }
+
+    fn uncover_row(&mut self, node: usize) {
+        let mut j = self.left[node];
+        while j != node {
+            let header = self.top[j];
+            if self.capacity[header] == 0 {
+                self.uncover(header);
+            }
+            self.capacity[header] += 1;
+            j = self.left[j];
+        }
+    }
+
+    /// The uncovered primary column with the fewest rows.
+    fn choose_column(&self) -> usize {
+        let mut best = self.right[ROOT];
+        let mut c = self.right[best];
+        while c != ROOT && self.len[best] > 0 {
+            if self.len[c] < self.len[best] {
+                best = c;
+            }
+            c = self.right[c];
+        }
+        best
+    }
+
+    /// Lazily enumerates all solutions as lists of row indices.
+    ///
+    /// The problem is restored when the iterator is dropped.
+    pub fn solutions(&mut self) -> Solutions<'_> {
+        Solutions {
+            problem: self,
+            stack: vec![],
+            state: State::Descend,
+        }
+    }
+
+    pub fn first_solution(&mut self) -> Option<Vec<usize>> {
+        self.solutions().next()
+    }
+
+    pub fn count_solutions(&mut self) -> usize {
+        self.solutions().count()
+    }
+
+    /// Calls `f` with every solution until it breaks.
+    pub fn for_each_solution<B>(
+        &mut self,
+        mut f: impl FnMut(&[usize]) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        for solution in self.solutions() {
+            f(&solution)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Number of primary columns.
+    #[inline]
+    pub fn primary(&self) -> usize {
+        self.primary
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    /// Every row on the stack is covered, pick the next column.
+    Descend,
+    /// The top of the stack is a candidate node whose row is not covered yet.
+    Try,
+    /// Every row on the stack is covered, move the top one along.
+    Backtrack,
+    Done,
+}
+
+/// Iterator over the solutions of an [`ExactCover`].
+pub struct Solutions<'p> {
+    problem: &'p mut ExactCover,
+    stack: Vec<usize>,
+    state: State,
+}
+
+impl Iterator for Solutions<'_> {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let p = &mut *self.problem;
+        loop {
+            match self.state {
+                State::Done => return None,
+                State::Descend => {
+                    if p.right[ROOT] == ROOT {
+                        self.state = State::Backtrack;
+                        return Some(self.stack.iter().map(|&node| p.row[node]).collect());
+                    }
+                    let column = p.choose_column();
+                    p.cover(column);
+                    self.stack.push(p.down[column]);
+                    self.state = State::Try;
+                }
+                State::Try => {
+                    let node = *self.stack.last().expect("empty stack");
+                    if p.top[node] == node {
+                        p.uncover(node);
+                        self.stack.pop();
+                        self.state = State::Backtrack;
+                    } else {
+                        p.cover_row(node);
+                        self.state = State::Descend;
+                    }
+                }
+                State::Backtrack => {
+                    let Some(node) = self.stack.last_mut() else {
+                        self.state = State::Done;
+                        continue;
+                    };
+                    p.uncover_row(*node);
+                    *node = p.down[*node];
+                    self.state = State::Try;
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Solutions<'_> {
+    fn drop(&mut self) {
+        let p = &mut *self.problem;
+        if self.state == State::Try
+            && let Some(node) = self.stack.pop()
+        {
+            p.uncover(p.top[node]);
+        }
+        while let Some(node) = self.stack.pop() {
+            p.uncover_row(node);
+            p.uncover(p.top[node]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn knuth_example() {
+        // The example from "Dancing Links", with columns A..G.
+        let mut problem = ExactCover::new(7, 0);
+        problem.add_row([2, 4, 5]);
+        problem.add_row([0, 3, 6]);
+        problem.add_row([1, 2, 5]);
+        problem.add_row([0, 3]);
+        problem.add_row([1, 6]);
+        problem.add_row([3, 4, 6]);
+
+        let mut solution = problem.first_solution().unwrap();
+        solution.sort();
+        assert_eq!(solution, vec![0, 3, 4]);
+        assert_eq!(problem.count_solutions(), 1);
+    }
+
+    #[test]
+    fn secondary_columns() {
+        // Both rows may be used as they only share a secondary column once.
+        let mut problem = ExactCover::new(2, 1);
+        problem.add_row([0, 2]);
+        problem.add_row([1]);
+        problem.add_row([1, 2]);
+        assert_eq!(problem.count_solutions(), 1);
+
+        let mut problem = ExactCover::new(2, 1);
+        problem.add_row([0, 2]);
+        problem.add_row([1, 2]);
+        assert_eq!(problem.count_solutions(), 0);
+    }
+
+    #[test]
+    fn capacity() {
+        // Three cells, filled by at most two pieces of one kind and one of
+        // another.
+        let mut problem = ExactCover::new(3, 2);
+        problem.set_capacity(3, 2);
+        for cell in 0..3 {
+            problem.add_row([cell, 3]);
+            problem.add_row([cell, 4]);
+        }
+        assert_eq!(problem.count_solutions(), 3);
+
+        let mut problem = ExactCover::new(3, 1);
+        problem.set_capacity(3, 2);
+        for cell in 0..3 {
+            problem.add_row([cell, 3]);
+        }
+        assert_eq!(problem.count_solutions(), 0);
+        problem.add_row([0, 1, 2]);
+        assert_eq!(problem.count_solutions(), 1);
+    }
+
+    #[test]
+    fn dropping_iterator_restores_problem() {
+        let mut problem = ExactCover::new(2, 0);
+        problem.add_row([0]);
+        problem.add_row([1]);
+        problem.add_row([0, 1]);
+        {
+            let mut solutions = problem.solutions();
+            solutions.next();
+        }
+        assert_eq!(problem.count_solutions(), 2);
+    }
+
+    #[test]
+    fn callback_stops_early() {
+        let mut problem = ExactCover::new(1, 0);
+        problem.add_row([0]);
+        problem.add_row([0]);
+        let mut seen = 0;
+        let result = problem.for_each_solution(|rows| {
+            seen += 1;
+            ControlFlow::Break(rows[0])
+        });
+        assert_eq!(result, ControlFlow::Break(0));
+        assert_eq!(seen, 1);
+    }
+
+    #[test]
+    fn sudoku() {
+        let puzzle =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+        let mut problem = ExactCover::new(4 * 81, 0);
+        let mut candidates = vec![];
+        for (cell, c) in puzzle.bytes().enumerate() {
+            let (r, col) = (cell / 9, cell % 9);
+            let b = (r / 3) * 3 + col / 3;
+            let digits = match c {
+                b'.' => 0..9,
+                c => {
+                    let d = (c - b'1') as usize;
+                    d..d + 1
+                }
+            };
+            for d in digits {
+                problem.add_row([cell, 81 + r * 9 + d, 162 + col * 9 + d, 243 + b * 9 + d]);
+                candidates.push((cell, d));
+            }
+        }
+
+        let solution = problem.first_solution().unwrap();
+        let mut grid = [0u8; 81];
+        for row in solution {
+            let (cell, d) = candidates[row];
+            grid[cell] = b'1' + d as u8;
+        }
+        assert_eq!(
+            std::str::from_utf8(&grid).unwrap(),
+            "534678912672195348198342567859761423426853791713924856961537284287419635345286179"
+        );
+        assert_eq!(problem.count_solutions(), 1);
+    }
+
+    fn orientations(cells: &[(i32, i32)]) -> Vec<Vec<(i32, i32)>> {
+        let mut all: Vec<Vec<(i32, i32)>> = vec![];
+        let mut shape = cells.to_vec();
+        for flip in 0..2 {
+            for _ in 0..4 {
+                shape = shape.iter().map(|&(r, c)| (c, -r)).collect();
+                let min_r = shape.iter().map(|c| c.0).min().unwrap();
+                let min_c = shape.iter().map(|c| c.1).min().unwrap();
+                let mut normalised: Vec<_> =
+                    shape.iter().map(|&(r, c)| (r - min_r, c - min_c)).collect();
+                normalised.sort();
+                if !all.contains(&normalised) {
+                    all.push(normalised);
+                }
+            }
+            if flip == 0 {
+                shape = shape.iter().map(|&(r, c)| (r, -c)).collect();
+            }
+        }
+        all
+    }
+
+    #[test]
+    fn pentominoes_3x20() {
+        let pentominoes: [&[(i32, i32)]; 12] = [
+            &[(0, 1), (0, 2), (1, 0), (1, 1), (2, 1)],
+            &[(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)],
+            &[(0, 0), (1, 0), (2, 0), (3, 0), (3, 1)],
+            &[(0, 1), (1, 1), (2, 0), (2, 1), (3, 0)],
+            &[(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)],
+            &[(0, 0), (0, 1), (0, 2), (1, 1), (2, 1)],
+            &[(0, 0), (0, 2), (1, 0), (1, 1), (1, 2)],
+            &[(0, 0), (1, 0), (2, 0), (2, 1), (2, 2)],
+            &[(0, 0), (1, 0), (1, 1), (2, 1), (2, 2)],
+            &[(0, 1), (1, 0), (1, 1), (1, 2), (2, 1)],
+            &[(0, 1), (1, 0), (1, 1), (2, 1), (3, 1)],
+            &[(0, 0), (0, 1), (1, 1), (2, 1), (2, 2)],
+        ];
+        let (height, width) = (3, 20);
+
+        let mut problem = ExactCover::new(12 + height * width, 0);
+        for (piece, cells) in pentominoes.iter().enumerate() {
+            for shape in orientations(cells) {
+                let h = shape.iter().map(|c| c.0).max().unwrap() + 1;
+                let w = shape.iter().map(|c| c.1).max().unwrap() + 1;
+                for r in 0..=height as i32 - h {
+                    for c in 0..=width as i32 - w {
+                        let cells = shape
+                            .iter()
+                            .map(|&(dr, dc)| 12 + ((r + dr) as usize) * width + (c + dc) as usize);
+                        problem.add_row(std::iter::once(piece).chain(cells));
+                    }
+                }
+            }
+        }
+
+        // Two distinct tilings, each in four mirror images.
+        assert_eq!(problem.count_solutions(), 8);
+    }
+}
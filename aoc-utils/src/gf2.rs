@@ -0,0 +1,416 @@
+//! Linear algebra over GF(2).
+//!
+//! Rows are [`BitSet`]s, so addition of rows is a word-wise XOR and matrices
+//! are not limited in width.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::bitset::BitSet;
+
+/// The most steps either search of [`BitMatrix::min_weight_solution`] may
+/// take, as a power of two.
+const MAX_SEARCH_BITS: u32 = 22;
+
+/// A dense matrix over GF(2).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitMatrix {
+    rows: Vec<BitSet>,
+    cols: usize,
+}
+
+/// The solution set of `A x = b`: every solution is `particular` plus a sum of
+/// vectors from `null_space`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solutions {
+    pub particular: BitSet,
+    pub null_space: Vec<BitSet>,
+}
+
+/// Why [`BitMatrix::min_weight_solution`] has no answer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MinWeightError {
+    /// `A x = rhs` has no solution.
+    Inconsistent,
+    /// Both the null space and the column space are too large to search.
+    TooLarge { rank: usize, nullity: usize },
+}
+
+impl fmt::Display for MinWeightError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inconsistent => write!(f, "no solution"),
+            Self::TooLarge { rank, nullity } => write!(
+                f,
+                "too many solutions to search, with rank {} and nullity {}",
+                rank, nullity
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MinWeightError {}
+
+/// Reduced row echelon form of a matrix.
+struct Echelon {
+    rows: Vec<BitSet>,
+    pivots: Vec<usize>,
+    free: Vec<usize>,
+}
+
+impl Echelon {
+    /// Brings `rows` into reduced row echelon form over the first `cols`
+    /// columns.
+    fn new(mut rows: Vec<BitSet>, cols: usize) -> Self {
+        let mut pivots = vec![];
+        let mut free = vec![];
+        for c in 0..cols {
+            let rank = pivots.len();
+            let Some(p) = (rank..rows.len()).find(|&r| rows[r].contains(c)) else {
+                free.push(c);
+                continue;
+            };
+            rows.swap(rank, p);
+            let pivot_row = rows[rank].clone();
+            for (r, row) in rows.iter_mut().enumerate() {
+                if r != rank && row.contains(c) {
+                    *row ^= &pivot_row;
+                }
+            }
+            pivots.push(c);
+        }
+        Self { rows, pivots, free }
+    }
+
+    fn null_basis(&self, cols: usize) -> Vec<BitSet> {
+        self.free
+            .iter()
+            .map(|&f| {
+                let mut v = BitSet::new(cols);
+                v.insert(f);
+                for (row, &p) in self.rows.iter().zip(&self.pivots) {
+                    if row.contains(f) {
+                        v.insert(p);
+                    }
+                }
+                v
+            })
+            .collect()
+    }
+}
+
+impl BitMatrix {
+    pub fn zeros(rows: usize, cols: usize) -> Self {
+        Self {
+            rows: vec![BitSet::new(cols); rows],
+            cols,
+        }
+    }
+
+    /// Builds a matrix from its rows, which must all have the same length.
+    pub fn from_rows(rows: Vec<BitSet>) -> Self {
+        let cols = rows.first().map_or(0, BitSet::len);
+        assert!(
+            rows.iter().all(|row| row.len() == cols),
+            "rows must have equal length"
+        );
+        Self { rows, cols }
+    }
+
+    /// Builds a matrix with `rows` rows from its columns.
+    pub fn from_columns<'c>(rows: usize, columns: impl IntoIterator<Item = &'c BitSet>) -> Self {
+        let columns: Vec<_> = columns.into_iter().collect();
+        let mut matrix = Self::zeros(rows, columns.len());
+        for (c, column) in columns.into_iter().enumerate() {
+            assert_eq!(column.len(), rows, "column length must match row count");
+            for r in column.iter_ones() {
+                matrix.set(r, c, true);
+            }
+        }
+        matrix
+    }
+
+    #[inline]
+    pub fn rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    #[inline]
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    #[inline]
+    pub fn row(&self, r: usize) -> &BitSet {
+        &self.rows[r]
+    }
+
+    #[inline]
+    pub fn get(&self, r: usize, c: usize) -> bool {
+        self.rows[r].contains(c)
+    }
+
+    #[inline]
+    pub fn set(&mut self, r: usize, c: usize, value: bool) {
+        self.rows[r].set(c, value);
+    }
+
+    pub fn rank(&self) -> usize {
+        Echelon::new(self.rows.clone(), self.cols).pivots.len()
+    }
+
+    /// The matrix in reduced row echelon form.
+    pub fn row_reduced(&self) -> Self {
+        Self {
+            rows: Echelon::new(self.rows.clone(), self.cols).rows,
+            cols: self.cols,
+        }
+    }
+
+    /// A basis of the vectors `x` with `A x = 0`.
+    pub fn null_space(&self) -> Vec<BitSet> {
+        Echelon::new(self.rows.clone(), self.cols).null_basis(self.cols)
+    }
+
+    /// All solutions of `A x = rhs`, or `None` if the system is inconsistent.
+    pub fn solve(&self, rhs: &BitSet) -> Option<Solutions> {
+        assert_eq!(rhs.len(), self.rows(), "rhs length must match row count");
+        let augmented = self
+            .rows
+            .iter()
+            .zip(rhs.iter())
+            .map(|(row, b)| {
+                let mut row = row.clone();
+                row.push(b);
+                row
+            })
+            .collect();
+        let echelon = Echelon::new(augmented, self.cols);
+
+        let rank = echelon.pivots.len();
+        if echelon.rows[rank..]
+            .iter()
+            .any(|row| row.contains(self.cols))
+        {
+            return None;
+        }
+
+        let mut particular = BitSet::new(self.cols);
+        for (row, &p) in echelon.rows.iter().zip(&echelon.pivots) {
+            particular.set(p, row.contains(self.cols));
+        }
+
+        Some(Solutions {
+            particular,
+            null_space: echelon.null_basis(self.cols),
+        })
+    }
+
+    /// The solution of `A x = rhs` with the fewest set bits.
+    ///
+    /// Searches whichever space is smaller: every combination of null space
+    /// vectors in Gray code order, `2^(cols - rank)` XORs, or the sums of
+    /// columns breadth first, at most `2^rank` of them each extended by every
+    /// column. Fails with [`MinWeightError::TooLarge`] when both take more
+    /// than `2^22` steps.
+    pub fn min_weight_solution(&self, rhs: &BitSet) -> Result<BitSet, MinWeightError> {
+        let solutions = self.solve(rhs).ok_or(MinWeightError::Inconsistent)?;
+        let nullity = solutions.null_space.len();
+        let rank = self.cols - nullity;
+        let walk_bits = nullity as u32;
+        let sums_bits = rank as u32 + (usize::BITS - self.cols.leading_zeros());
+        if walk_bits <= sums_bits.min(MAX_SEARCH_BITS) {
+            Ok(Self::lightest_in_coset(solutions))
+        } else if sums_bits <= MAX_SEARCH_BITS {
+            Ok(self.fewest_columns(rhs))
+        } else {
+            Err(MinWeightError::TooLarge { rank, nullity })
+        }
+    }
+
+    /// The lightest of all solutions, by walking the null space in Gray code
+    /// order.
+    fn lightest_in_coset(solutions: Solutions) -> BitSet {
+        let Solutions {
+            particular,
+            null_space,
+        } = solutions;
+        let mut current = particular.clone();
+        let mut best = particular;
+        for i in 1u64..1 << null_space.len() {
+            current ^= &null_space[i.trailing_zeros() as usize];
+            if current.count_ones() < best.count_ones() {
+                best.clone_from(&current);
+            }
+        }
+        best
+    }
+
+    /// The fewest columns that sum to `rhs`, which must be in their span, by
+    /// a breadth first search over their sums.
+    ///
+    /// A shortest sum never uses a column twice, as the two would cancel.
+    fn fewest_columns(&self, rhs: &BitSet) -> BitSet {
+        let columns: Vec<BitSet> = (0..self.cols)
+            .map(|c| {
+                BitSet::from_indices(self.rows(), (0..self.rows()).filter(|&r| self.get(r, c)))
+            })
+            .collect();
+        // Each sum reached, with the sum it extends and the column added.
+        let mut sums = vec![(BitSet::new(self.rows()), usize::MAX, usize::MAX)];
+        let mut seen = HashMap::from([(sums[0].0.clone(), 0)]);
+        let mut next = 0;
+        let mut found = seen.get(rhs).copied();
+        while found.is_none() {
+            let sum = sums[next].0.clone();
+            for (c, column) in columns.iter().enumerate() {
+                let extended = &sum ^ column;
+                if !seen.contains_key(&extended) {
+                    seen.insert(extended.clone(), sums.len());
+                    if &extended == rhs {
+                        found = Some(sums.len());
+                    }
+                    sums.push((extended, next, c));
+                }
+            }
+            next += 1;
+        }
+
+        let mut solution = BitSet::new(self.cols);
+        let mut at = found.expect("rhs is reachable");
+        while at != 0 {
+            let (_, parent, column) = sums[at];
+            solution.insert(column);
+            at = parent;
+        }
+        solution
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix(rows: &[&str]) -> BitMatrix {
+        let mut m = BitMatrix::zeros(rows.len(), rows[0].len());
+        for (r, row) in rows.iter().enumerate() {
+            for (c, b) in row.bytes().enumerate() {
+                m.set(r, c, b == b'1');
+            }
+        }
+        m
+    }
+
+    fn mul(m: &BitMatrix, x: &BitSet) -> BitSet {
+        BitSet::from_indices(
+            m.rows(),
+            (0..m.rows()).filter(|&r| (m.row(r) & x).count_ones() % 2 == 1),
+        )
+    }
+
+    #[test]
+    fn rank_and_reduce() {
+        let m = matrix(&["110", "011", "101"]);
+        assert_eq!(m.rank(), 2);
+        assert_eq!(m.row_reduced(), matrix(&["101", "011", "000"]));
+    }
+
+    #[test]
+    fn null_space_is_annihilated() {
+        let m = matrix(&["1100", "0110", "0011"]);
+        let null_space = m.null_space();
+        assert_eq!(null_space.len(), 1);
+        for v in null_space {
+            assert!(mul(&m, &v).none());
+        }
+    }
+
+    #[test]
+    fn inconsistent() {
+        let m = matrix(&["11", "11"]);
+        assert_eq!(m.solve(&"[#.]".parse().unwrap()), None);
+    }
+
+    #[test]
+    fn min_weight() {
+        // Buttons (3) (1,3) (2) (2,3) (0,2) (0,1) with lights [.##.]
+        let buttons: Vec<BitSet> = ["[...#]", "[.#.#]", "[..#.]", "[..##]", "[#.#.]", "[##..]"]
+            .iter()
+            .map(|b| b.parse().unwrap())
+            .collect();
+        let m = BitMatrix::from_columns(4, &buttons);
+        let rhs = "[.##.]".parse().unwrap();
+        let x = m.min_weight_solution(&rhs).unwrap();
+        assert_eq!(mul(&m, &x), rhs);
+        assert_eq!(x.count_ones(), 2);
+    }
+
+    #[test]
+    fn searches_agree() {
+        let mut rng = crate::rng::Rng::new(7);
+        for _ in 0..200 {
+            let (rows, cols) = (rng.range(1..=6) as usize, rng.range(1..=10) as usize);
+            let mut m = BitMatrix::zeros(rows, cols);
+            for r in 0..rows {
+                for c in 0..cols {
+                    m.set(r, c, rng.chance(0.4));
+                }
+            }
+            let rhs = BitSet::from_indices(rows, (0..rows).filter(|_| rng.chance(0.5)));
+            let lightest = (0..1u32 << cols)
+                .map(|bits| BitSet::from_indices(cols, (0..cols).filter(|c| bits >> c & 1 == 1)))
+                .filter(|x| mul(&m, x) == rhs)
+                .map(|x| x.count_ones())
+                .min();
+            let Some(solutions) = m.solve(&rhs) else {
+                assert_eq!(lightest, None);
+                continue;
+            };
+            let walked = BitMatrix::lightest_in_coset(solutions);
+            let summed = m.fewest_columns(&rhs);
+            assert_eq!(mul(&m, &walked), rhs);
+            assert_eq!(mul(&m, &summed), rhs);
+            assert_eq!(Some(walked.count_ones()), lightest);
+            assert_eq!(Some(summed.count_ones()), lightest);
+        }
+    }
+
+    #[test]
+    fn redundant_columns() {
+        // Every non-zero column of height 3, 20 times over.
+        let columns: Vec<_> = (0..140)
+            .map(|c| BitSet::from_indices(3, (0..3).filter(|r| (c % 7 + 1) >> r & 1 == 1)))
+            .collect();
+        let m = BitMatrix::from_columns(3, &columns);
+        let x = m.min_weight_solution(&"[###]".parse().unwrap()).unwrap();
+        assert_eq!(x, BitSet::from_indices(140, [6]));
+        assert_eq!(m.min_weight_solution(&BitSet::new(3)), Ok(BitSet::new(140)));
+
+        // Rank 30 and nullity 30.
+        let columns: Vec<_> = (0..60)
+            .map(|c| BitSet::from_indices(30, [c % 30, (c + c / 30) % 30]))
+            .collect();
+        let m = BitMatrix::from_columns(30, &columns);
+        assert_eq!(
+            m.min_weight_solution(&BitSet::from_indices(30, [0])),
+            Err(MinWeightError::TooLarge {
+                rank: 30,
+                nullity: 30
+            })
+        );
+        assert_eq!(
+            BitMatrix::from_columns(2, &[BitSet::new(2)])
+                .min_weight_solution(&"[#.]".parse().unwrap()),
+            Err(MinWeightError::Inconsistent)
+        );
+    }
+
+    #[test]
+    fn wide_matrix() {
+        let columns: Vec<_> = (0..100).map(|c| BitSet::from_indices(100, [c])).collect();
+        let m = BitMatrix::from_columns(100, &columns);
+        let rhs = BitSet::from_indices(100, (0..100).filter(|i| i % 3 == 0));
+        let x = m.min_weight_solution(&rhs).unwrap();
+        assert_eq!(x, rhs);
+    }
+}
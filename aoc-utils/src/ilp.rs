@@ -0,0 +1,553 @@
+//! Exact integer linear programming for small equality systems.
+//!
+//! Solves `A x = b` for non-negative integer `x` minimising `sum(x)`. The
+//! system is brought to reduced row echelon form over the rationals, which
+//! expresses every pivot variable in terms of the free variables. The free
+//! variables are then searched by branch and bound: the pivot rows narrow the
+//! range of each free variable so that the pivots can stay within their
+//! bounds, and the objective, which is linear in the free variables, bounds
+//! what the rest of the search can still achieve.
+
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Rational {
+    num: i128,
+    den: i128,
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+impl Rational {
+    fn new(num: i128, den: i128) -> Self {
+        assert_ne!(den, 0, "zero denominator");
+        let g = gcd(num, den).max(1);
+        let sign = den.signum();
+        Self {
+            num: sign * num / g,
+            den: sign * den / g,
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.num == 0
+    }
+
+    fn as_integer(&self) -> Option<i128> {
+        (self.den == 1).then_some(self.num)
+    }
+
+    fn floor(&self) -> i128 {
+        self.num.div_euclid(self.den)
+    }
+
+    fn ceil(&self) -> i128 {
+        -(-*self).floor()
+    }
+}
+
+impl From<i64> for Rational {
+    fn from(value: i64) -> Self {
+        Self {
+            num: value as i128,
+            den: 1,
+        }
+    }
+}
+
+impl Add for Rational {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.num * rhs.den + rhs.num * self.den, self.den * rhs.den)
+    }
+}
+
+impl Sub for Rational {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + -rhs
+    }
+}
+
+impl Neg for Rational {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            num: -self.num,
+            den: self.den,
+        }
+    }
+}
+
+impl Mul for Rational {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::new(self.num * rhs.num, self.den * rhs.den)
+    }
+}
+
+impl Div for Rational {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        Self::new(self.num * rhs.den, self.den * rhs.num)
+    }
+}
+
+/// A pivot variable expressed as `constant - sum(coeffs[k] * free[k])`.
+#[derive(Debug)]
+struct PivotRow {
+    var: usize,
+    constant: Rational,
+    coeffs: Vec<Rational>,
+}
+
+/// A system of linear equations `A x = b` over non-negative integers.
+///
+/// Every variable needs an upper bound for the search to terminate. Bounds are
+/// inferred from rows with only non-negative coefficients, and can be given
+/// explicitly with [`LinearSystem::with_upper_bound`].
+#[derive(Clone, Debug)]
+pub struct LinearSystem {
+    matrix: Vec<Vec<i64>>,
+    rhs: Vec<i64>,
+    bounds: Vec<Option<u64>>,
+}
+
+impl LinearSystem {
+    /// Creates the system `matrix * x = rhs`, where `matrix` is given row by row.
+    pub fn new(matrix: Vec<Vec<i64>>, rhs: Vec<i64>) -> Self {
+        assert_eq!(matrix.len(), rhs.len(), "row count mismatch");
+        let num_vars = matrix.first().map_or(0, Vec::len);
+        assert!(
+            matrix.iter().all(|row| row.len() == num_vars),
+            "rows must have equal length"
+        );
+
+        let mut bounds = vec![None; num_vars];
+        for (row, &target) in matrix.iter().zip(&rhs) {
+            if target < 0 || row.iter().any(|&c| c < 0) {
+                continue;
+            }
+            for (bound, &c) in bounds.iter_mut().zip(row) {
+                if c > 0 {
+                    let b = (target / c) as u64;
+                    *bound = Some(bound.map_or(b, |prev: u64| prev.min(b)));
+                }
+            }
+        }
+
+        Self {
+            matrix,
+            rhs,
+            bounds,
+        }
+    }
+
+    /// Tightens the upper bound of variable `var` to `bound`.
+    pub fn with_upper_bound(mut self, var: usize, bound: u64) -> Self {
+        let prev = self.bounds[var].unwrap_or(u64::MAX);
+        self.bounds[var] = Some(prev.min(bound));
+        self
+    }
+
+    #[inline]
+    pub fn num_vars(&self) -> usize {
+        self.bounds.len()
+    }
+
+    /// Reduces the augmented matrix and returns the pivot rows together with
+    /// the indices of the free variables, or `None` if the system is
+    /// inconsistent.
+    fn reduce(&self) -> Option<(Vec<PivotRow>, Vec<usize>)> {
+        let num_vars = self.num_vars();
+        let mut rows: Vec<Vec<Rational>> = self
+            .matrix
+            .iter()
+            .zip(&self.rhs)
+            .map(|(row, &b)| row.iter().copied().chain([b]).map(Rational::from).collect())
+            .collect();
+
+        let mut pivots = vec![];
+        let mut free = vec![];
+        let mut rank = 0;
+        for col in 0..num_vars {
+            let Some(pivot) = (rank..rows.len()).find(|&r| !rows[r][col].is_zero()) else {
+                free.push(col);
+                continue;
+            };
+            rows.swap(rank, pivot);
+
+            let scale = rows[rank][col];
+            for v in rows[rank].iter_mut() {
+                *v = *v / scale;
+            }
+            let pivot_row = rows[rank].clone();
+            for (r, row) in rows.iter_mut().enumerate() {
+                if r == rank || row[col].is_zero() {
+                    continue;
+                }
+                let factor = row[col];
+                for (v, &p) in row.iter_mut().zip(&pivot_row).skip(col) {
+                    *v = *v - factor * p;
+                }
+            }
+            pivots.push(col);
+            rank += 1;
+        }
+
+        if rows[rank..].iter().any(|row| !row[num_vars].is_zero()) {
+            return None;
+        }
+
+        let pivot_rows = pivots
+            .into_iter()
+            .zip(rows)
+            .map(|(var, row)| PivotRow {
+                var,
+                constant: row[num_vars],
+                coeffs: free.iter().map(|&f| row[f]).collect(),
+            })
+            .collect();
+        Some((pivot_rows, free))
+    }
+
+    /// Finds the non-negative integer solution with the smallest sum of
+    /// variables, or `None` if there is none or a free variable has no upper
+    /// bound.
+    pub fn minimize_sum(&self) -> Option<Vec<u64>> {
+        let (pivot_rows, free) = self.reduce()?;
+        let free_bounds = free
+            .iter()
+            .enumerate()
+            .map(|(k, &f)| self.bounds[f].or_else(|| free_bound(&pivot_rows, k)))
+            .collect::<Option<Vec<u64>>>()?;
+
+        // The sum of all variables is `base + sum(weights[k] * free[k])`.
+        let one = Rational::from(1);
+        let base = pivot_rows
+            .iter()
+            .fold(Rational::from(0), |acc, row| acc + row.constant);
+        let weights: Vec<Rational> = (0..free.len())
+            .map(|k| pivot_rows.iter().fold(one, |acc, row| acc - row.coeffs[k]))
+            .collect();
+
+        // The range of `-sum(coeffs[k] * free[k])` over the free variables
+        // after `depth`, for each row and depth.
+        let suffix_ranges = pivot_rows
+            .iter()
+            .map(|row| {
+                let mut ranges = vec![(Rational::from(0), Rational::from(0)); free.len() + 1];
+                for k in (0..free.len()).rev() {
+                    let extreme = -row.coeffs[k] * Rational::from(free_bounds[k] as i64);
+                    let (lo, hi) = ranges[k + 1];
+                    ranges[k] = if extreme.num < 0 {
+                        (lo + extreme, hi)
+                    } else {
+                        (lo, hi + extreme)
+                    };
+                }
+                ranges
+            })
+            .collect();
+        // The smallest change to the objective the free variables after
+        // `depth` can make.
+        let mut suffix_gain = vec![Rational::from(0); free.len() + 1];
+        for k in (0..free.len()).rev() {
+            let extreme = weights[k] * Rational::from(free_bounds[k] as i64);
+            suffix_gain[k] = suffix_gain[k + 1]
+                + if extreme.num < 0 {
+                    extreme
+                } else {
+                    Rational::from(0)
+                };
+        }
+
+        let mut search = Search {
+            pivot_rows: &pivot_rows,
+            pivot_bounds: pivot_rows.iter().map(|row| self.bounds[row.var]).collect(),
+            free_bounds: &free_bounds,
+            weights: &weights,
+            suffix_ranges,
+            suffix_gain,
+            values: pivot_rows.iter().map(|row| row.constant).collect(),
+            assignment: vec![0; free.len()],
+            best: None,
+        };
+        search.run(0, base);
+
+        let (_, free_values, pivot_values) = search.best?;
+        let mut solution = vec![0; self.num_vars()];
+        for (&f, v) in free.iter().zip(free_values) {
+            solution[f] = v;
+        }
+        for (row, v) in pivot_rows.iter().zip(pivot_values) {
+            solution[row.var] = v;
+        }
+        Some(solution)
+    }
+}
+
+/// An upper bound on free variable `k` from the pivot rows, which holds at
+/// least for the solutions with the smallest sum.
+fn free_bound(pivot_rows: &[PivotRow], k: usize) -> Option<u64> {
+    // A variable in no row only adds to the sum.
+    if pivot_rows.iter().all(|row| row.coeffs[k].is_zero()) {
+        return Some(0);
+    }
+    // A pivot `constant - sum(coeffs * free)` with no negative coefficients
+    // can only stay non-negative while `coeffs[k] * free[k] <= constant`.
+    pivot_rows
+        .iter()
+        .filter(|row| row.coeffs[k].num > 0 && row.coeffs.iter().all(|c| c.num >= 0))
+        .map(|row| (row.constant / row.coeffs[k]).floor().max(0) as u64)
+        .min()
+}
+
+struct Search<'s> {
+    pivot_rows: &'s [PivotRow],
+    pivot_bounds: Vec<Option<u64>>,
+    free_bounds: &'s [u64],
+    /// How much each free variable adds to the objective per unit.
+    weights: &'s [Rational],
+    suffix_ranges: Vec<Vec<(Rational, Rational)>>,
+    suffix_gain: Vec<Rational>,
+    /// The value of each pivot variable with the free variables not yet
+    /// assigned at zero.
+    values: Vec<Rational>,
+    assignment: Vec<u64>,
+    best: Option<(u64, Vec<u64>, Vec<u64>)>,
+}
+
+impl Search<'_> {
+    /// Whether a solution with objective at least `total` could beat the
+    /// best so far, as objectives of solutions are integers.
+    fn is_better(&self, total: Rational) -> bool {
+        self.best
+            .as_ref()
+            .is_none_or(|(best, _, _)| total.ceil() < *best as i128)
+    }
+
+    /// The values free variable `depth` can take while every pivot variable
+    /// can still end up within its bounds, or `None` if there are none.
+    fn range(&self, depth: usize) -> Option<(i128, i128)> {
+        let (mut lo, mut hi) = (0, self.free_bounds[depth] as i128);
+        for (r, row) in self.pivot_rows.iter().enumerate() {
+            let c = row.coeffs[depth];
+            let (rest_lo, rest_hi) = self.suffix_ranges[r][depth + 1];
+            // The pivot is `value - c * x + rest` with `rest` in its range,
+            // and must be able to reach `0..=bound`.
+            let most = self.values[r] + rest_hi;
+            let least = self.pivot_bounds[r]
+                .map(|bound| self.values[r] + rest_lo - Rational::from(bound as i64));
+            if c.is_zero() {
+                if most.num < 0 || least.is_some_and(|least| least.num > 0) {
+                    return None;
+                }
+            } else if c.num > 0 {
+                hi = hi.min((most / c).floor());
+                if let Some(least) = least {
+                    lo = lo.max((least / c).ceil());
+                }
+            } else {
+                lo = lo.max((most / c).ceil());
+                if let Some(least) = least {
+                    hi = hi.min((least / c).floor());
+                }
+            }
+        }
+        (lo <= hi).then_some((lo, hi))
+    }
+
+    /// Searches the free variables from `depth` on, where `objective` is the
+    /// sum of all variables with those still at zero.
+    fn run(&mut self, depth: usize, objective: Rational) {
+        if depth == self.assignment.len() {
+            let pivots = self
+                .values
+                .iter()
+                .zip(&self.pivot_bounds)
+                .map(|(value, bound)| {
+                    let value = u64::try_from(value.as_integer()?).ok()?;
+                    bound.is_none_or(|bound| value <= bound).then_some(value)
+                })
+                .collect::<Option<Vec<u64>>>();
+            if let Some(pivots) = pivots
+                && self.is_better(objective)
+            {
+                let total = objective.as_integer().expect("integer variables") as u64;
+                self.best = Some((total, self.assignment.clone(), pivots));
+            }
+            return;
+        }
+
+        let Some((lo, hi)) = self.range(depth) else {
+            return;
+        };
+        // Try the values that lower the objective first, so that a good
+        // solution is found early and prunes the rest.
+        let weight = self.weights[depth];
+        let values: Box<dyn Iterator<Item = i128>> = if weight.num < 0 {
+            Box::new((lo..=hi).rev())
+        } else {
+            Box::new(lo..=hi)
+        };
+        for value in values {
+            let x = Rational::from(value as i64);
+            let objective = objective + weight * x;
+            if !self.is_better(objective + self.suffix_gain[depth + 1]) {
+                // The objective only grows along this direction.
+                break;
+            }
+            self.assignment[depth] = value as u64;
+            for (v, row) in self.values.iter_mut().zip(self.pivot_rows) {
+                *v = *v - row.coeffs[depth] * x;
+            }
+            self.run(depth + 1, objective);
+            for (v, row) in self.values.iter_mut().zip(self.pivot_rows) {
+                *v = *v + row.coeffs[depth] * x;
+            }
+        }
+        self.assignment[depth] = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    #[test]
+    fn rational_normalises() {
+        assert_eq!(Rational::new(2, -4), Rational::new(-1, 2));
+        assert_eq!(Rational::new(3, 3), Rational::from(1));
+    }
+
+    #[test]
+    fn unique_solution() {
+        // x + y = 3, x - y = 1
+        let system = LinearSystem::new(vec![vec![1, 1], vec![1, -1]], vec![3, 1])
+            .with_upper_bound(0, 3)
+            .with_upper_bound(1, 3);
+        assert_eq!(system.minimize_sum(), Some(vec![2, 1]));
+    }
+
+    #[test]
+    fn minimises_over_free_variables() {
+        // Buttons (3) (1,3) (2) (2,3) (0,2) (0,1) with joltage {3,5,4,7}.
+        let buttons: [&[usize]; 6] = [&[3], &[1, 3], &[2], &[2, 3], &[0, 2], &[0, 1]];
+        let targets = vec![3, 5, 4, 7];
+        let matrix = (0..targets.len())
+            .map(|i| buttons.iter().map(|b| b.contains(&i) as i64).collect())
+            .collect();
+        let solution = LinearSystem::new(matrix, targets).minimize_sum().unwrap();
+        assert_eq!(solution.iter().sum::<u64>(), 10);
+    }
+
+    #[test]
+    fn inconsistent_system() {
+        let system = LinearSystem::new(vec![vec![1, 1], vec![1, 1]], vec![1, 2]);
+        assert_eq!(system.minimize_sum(), None);
+    }
+
+    #[test]
+    fn no_integer_solution() {
+        let system = LinearSystem::new(vec![vec![2]], vec![3]);
+        assert_eq!(system.minimize_sum(), None);
+    }
+
+    #[test]
+    fn unbounded_free_variable() {
+        // x - y = 0 bounds neither variable.
+        let system = LinearSystem::new(vec![vec![1, -1]], vec![0]);
+        assert_eq!(system.minimize_sum(), None);
+        assert_eq!(
+            system.with_upper_bound(1, 5).minimize_sum(),
+            Some(vec![0, 0])
+        );
+    }
+
+    /// A random system of buttons wired to lights, and the joltages reached
+    /// by pressing each button up to `presses` times.
+    fn random_manual(
+        rng: &mut Rng,
+        lights: usize,
+        buttons: usize,
+        presses: u64,
+    ) -> (LinearSystem, u64) {
+        let matrix: Vec<Vec<i64>> = (0..lights)
+            .map(|_| (0..buttons).map(|_| rng.chance(0.4) as i64).collect())
+            .collect();
+        let counts: Vec<i64> = (0..buttons)
+            .map(|_| rng.below(presses + 1) as i64)
+            .collect();
+        let targets = matrix
+            .iter()
+            .map(|row| row.iter().zip(&counts).map(|(a, x)| a * x).sum())
+            .collect();
+        (
+            LinearSystem::new(matrix, targets),
+            counts.iter().sum::<i64>() as u64,
+        )
+    }
+
+    #[test]
+    fn matches_exhaustive_search() {
+        let mut rng = Rng::new(4);
+        for _ in 0..200 {
+            let (system, _) = random_manual(&mut rng, 3, 5, 3);
+            let bounds: Vec<u64> = system.bounds.iter().map(|b| b.unwrap_or(0)).collect();
+            let mut best = None;
+            let mut x = vec![0u64; 5];
+            'search: loop {
+                let solves = system.matrix.iter().zip(&system.rhs).all(|(row, &b)| {
+                    row.iter().zip(&x).map(|(&a, &x)| a * x as i64).sum::<i64>() == b
+                });
+                if solves {
+                    let sum: u64 = x.iter().sum();
+                    best = Some(best.map_or(sum, |best: u64| best.min(sum)));
+                }
+                for (v, &bound) in x.iter_mut().zip(&bounds) {
+                    if *v < bound {
+                        *v += 1;
+                        continue 'search;
+                    }
+                    *v = 0;
+                }
+                break;
+            }
+            let solution = system.minimize_sum();
+            assert_eq!(
+                solution.map(|s| s.iter().sum::<u64>()),
+                best,
+                "{:?}",
+                system
+            );
+        }
+    }
+
+    #[test]
+    fn large_manuals() {
+        // Joltages around 100 leave several free variables with wide ranges.
+        let mut rng = Rng::new(5);
+        for _ in 0..20 {
+            let (system, pressed) = random_manual(&mut rng, 9, 13, 25);
+            let solution = system.minimize_sum().unwrap();
+            for (row, &b) in system.matrix.iter().zip(&system.rhs) {
+                let sum: i64 = row.iter().zip(&solution).map(|(&a, &x)| a * x as i64).sum();
+                assert_eq!(sum, b);
+            }
+            assert!(solution.iter().sum::<u64>() <= pressed);
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! Allocation-free extraction of the integers embedded in text.
+//!
+//! Everything that is not part of a number is a separator, so `162,817,812`,
+//! `7x3: 1 0` and `Button A: X+94, Y-34` all scan without a grammar. A `-`
+//! is a sign only for signed types, and only when it directly precedes a
+//! digit and does not follow one, so ranges such as `3-5` scan as two
+//! positive numbers.
+
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+
+use crate::parse::ParseReport;
+
+/// Integer types that can be scanned from decimal digits.
+pub trait Integer: Copy {
+    const SIGNED: bool;
+    const NAME: &'static str;
+
+    /// Appends a decimal digit, returning `None` on overflow. Negative
+    /// numbers accumulate downwards so that the minimum value fits.
+    fn push_digit(self, digit: u8, negative: bool) -> Option<Self>;
+
+    fn zero() -> Self;
+}
+
+macro_rules! impl_integer {
+    ($signed:literal => $($t:ty),*) => {$(
+        impl Integer for $t {
+            const SIGNED: bool = $signed;
+            const NAME: &'static str = stringify!($t);
+
+            #[inline]
+            fn push_digit(self, digit: u8, negative: bool) -> Option<Self> {
+                let digit = digit as Self;
+                let shifted = self.checked_mul(10)?;
+                if negative {
+                    shifted.checked_sub(digit)
+                } else {
+                    shifted.checked_add(digit)
+                }
+            }
+
+            #[inline]
+            fn zero() -> Self {
+                0
+            }
+        }
+    )*};
+}
+
+impl_integer!(false => u8, u16, u32, u64, u128, usize);
+impl_integer!(true => i8, i16, i32, i64, i128, isize);
+
+/// A number in the scanned text that does not fit the requested type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Overflow {
+    /// Byte offset of the number, including its sign.
+    pub offset: usize,
+}
+
+/// Iterator over the integers in a string, see [`ints`].
+#[derive(Clone, Debug)]
+pub struct Ints<'a, T> {
+    bytes: &'a [u8],
+    pos: usize,
+    _marker: PhantomData<T>,
+}
+
+/// The integers in `s`, in order.
+///
+/// Panics on a number that does not fit in `T`, use [`Ints::next_checked`]
+/// to handle that instead.
+pub fn ints<T: Integer>(s: &str) -> Ints<'_, T> {
+    Ints {
+        bytes: s.as_bytes(),
+        pos: 0,
+        _marker: PhantomData,
+    }
+}
+
+impl<T: Integer> Ints<'_, T> {
+    /// The next integer, or its offset if it does not fit in `T`.
+    pub fn next_checked(&mut self) -> Option<Result<T, Overflow>> {
+        let bytes = self.bytes;
+        let start = self.pos + bytes[self.pos..].iter().position(u8::is_ascii_digit)?;
+
+        let negative = T::SIGNED
+            && start > 0
+            && bytes[start - 1] == b'-'
+            && (start < 2 || !bytes[start - 2].is_ascii_digit());
+        let offset = if negative { start - 1 } else { start };
+
+        let mut value = T::zero();
+        let mut end = start;
+        let mut overflow = false;
+        while let Some(&b) = bytes.get(end).filter(|b| b.is_ascii_digit()) {
+            match value.push_digit(b - b'0', negative) {
+                Some(next) => value = next,
+                None => overflow = true,
+            }
+            end += 1;
+        }
+        self.pos = end;
+
+        Some(if overflow {
+            Err(Overflow { offset })
+        } else {
+            Ok(value)
+        })
+    }
+}
+
+impl<T: Integer> Iterator for Ints<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.next_checked().map(|value| {
+            value.unwrap_or_else(|o| panic!("number at byte {} overflows {}", o.offset, T::NAME))
+        })
+    }
+}
+
+impl<T: Integer> FusedIterator for Ints<'_, T> {}
+
+/// Values built from a fixed number of integers on one line.
+pub trait FromInts: Sized {
+    /// How many integers the line must contain.
+    const COUNT: usize;
+
+    /// Reads exactly [`FromInts::COUNT`] integers from `line`, giving the byte
+    /// offset in `line` and a description of what was expected on failure.
+    fn from_line(line: &str) -> Result<Self, (usize, String)>;
+}
+
+impl<T: Integer, const N: usize> FromInts for [T; N] {
+    const COUNT: usize = N;
+
+    fn from_line(line: &str) -> Result<Self, (usize, String)> {
+        let mut values = [T::zero(); N];
+        let mut scan = ints::<T>(line);
+        for (i, value) in values.iter_mut().enumerate() {
+            *value = match scan.next_checked() {
+                Some(Ok(v)) => v,
+                Some(Err(o)) => return Err((o.offset, format!("an integer fitting {}", T::NAME))),
+                None => return Err((line.len(), format!("{} integers, found {}", N, i))),
+            };
+        }
+        if scan.next_checked().is_some() {
+            // Point at the extra number rather than past it.
+            let extra = line[..scan.pos]
+                .char_indices()
+                .rfind(|&(_, c)| !c.is_ascii_digit() && c != '-')
+                .map_or(0, |(i, c)| i + c.len_utf8());
+            return Err((extra, format!("only {} integers", N)));
+        }
+        Ok(values)
+    }
+}
+
+/// One `R` per non-blank line of `input`, e.g. `lines_of::<[u32; 3]>` for
+/// lines like `162,817,812`.
+pub fn lines_of<R: FromInts>(input: &str) -> Result<Vec<R>, ParseReport> {
+    let mut out = Vec::with_capacity(input.len() / (R::COUNT * 4).max(1));
+    let mut line_start = 0;
+    for line in input.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\n', '\r']);
+        if !content.trim().is_empty() {
+            let value = R::from_line(content).map_err(|(offset, expected)| {
+                ParseReport::expected(input, line_start + offset, expected)
+            })?;
+            out.push(value);
+        }
+        line_start += line.len();
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn separators_and_signs() {
+        let actual: Vec<i32> = ints("Button A: X+94, Y-34 3-5 -7").collect();
+        assert_eq!(actual, vec![94, -34, 3, 5, -7]);
+
+        let actual: Vec<u8> = ints("-1,2").collect();
+        assert_eq!(actual, vec![1, 2]);
+
+        assert_eq!(ints::<i8>("-128").collect::<Vec<_>>(), vec![i8::MIN]);
+        assert_eq!(ints::<u64>("no numbers").next(), None);
+    }
+
+    #[test]
+    fn overflow_is_reported() {
+        let mut scan = ints::<u8>("1 256 3");
+        assert_eq!(scan.next_checked(), Some(Ok(1)));
+        assert_eq!(scan.next_checked(), Some(Err(Overflow { offset: 2 })));
+        assert_eq!(scan.next_checked(), Some(Ok(3)));
+        assert_eq!(scan.next_checked(), None);
+    }
+
+    #[test]
+    fn lines_of_arrays() {
+        let actual = lines_of::<[u32; 3]>("162,817,812\n57,618,57\n\n");
+        assert_eq!(actual, Ok(vec![[162, 817, 812], [57, 618, 57]]));
+    }
+
+    #[test]
+    fn lines_of_count_mismatch() {
+        let report = lines_of::<[u32; 2]>("1,2\n3\n").unwrap_err();
+        assert_eq!((report.line, report.column), (2, 2));
+        assert_eq!(report.expected, vec!["2 integers, found 1"]);
+
+        let report = lines_of::<[u32; 2]>("1,2\r\n3,4,56\r\n").unwrap_err();
+        assert_eq!((report.line, report.column), (2, 5));
+        assert_eq!(report.source_line, "3,4,56");
+
+        let report = lines_of::<[u32; 1]>("1é2").unwrap_err();
+        assert_eq!((report.line, report.column), (1, 3));
+
+        let report = lines_of::<[u8; 1]>("300").unwrap_err();
+        assert_eq!(report.expected, vec!["an integer fitting u8"]);
+    }
+}
@@ -0,0 +1,229 @@
+//! A k-d tree over integer points, for nearest neighbour queries.
+//!
+//! Besides the `k` nearest points to a query, the tree can stream every pair
+//! of its points by increasing distance without materialising all
+//! `n (n - 1) / 2` of them. Each point keeps a cursor into its own nearest
+//! neighbours, fetched in growing batches, and a heap merges the cursors.
+//!
+//! Distances are squared Euclidean and exact for coordinates within
+//! `±2^62`. Ties are broken by index, so results are deterministic.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+pub type Point<const D: usize> = [i64; D];
+
+/// Neighbours fetched at first for each point when streaming pairs.
+const INITIAL_NEIGHBOURS: usize = 8;
+
+pub fn distance<const D: usize>(a: &Point<D>, b: &Point<D>) -> u128 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a.abs_diff(*b) as u128).pow(2))
+        .sum()
+}
+
+#[derive(Clone, Debug)]
+pub struct KdTree<const D: usize> {
+    points: Vec<Point<D>>,
+    /// Point indices where every range has its median in the middle, split
+    /// on the axis given by the depth of the range.
+    order: Vec<usize>,
+}
+
+impl<const D: usize> KdTree<D> {
+    pub fn new(points: Vec<Point<D>>) -> Self {
+        let mut order: Vec<_> = (0..points.len()).collect();
+        Self::build(&points, &mut order, 0);
+        Self { points, order }
+    }
+
+    fn build(points: &[Point<D>], order: &mut [usize], depth: usize) {
+        if order.len() <= 1 {
+            return;
+        }
+        let mid = order.len() / 2;
+        order.select_nth_unstable_by_key(mid, |&i| points[i][depth % D]);
+        let (left, right) = order.split_at_mut(mid);
+        Self::build(points, left, depth + 1);
+        Self::build(points, &mut right[1..], depth + 1);
+    }
+
+    pub fn points(&self) -> &[Point<D>] {
+        &self.points
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// The `k` points nearest to `query` as `(distance, index)`, nearest
+    /// first.
+    pub fn nearest(&self, query: &Point<D>, k: usize) -> Vec<(u128, usize)> {
+        let mut best = BinaryHeap::with_capacity(k + 1);
+        if k > 0 {
+            self.search(0, self.order.len(), 0, query, k, &mut best);
+        }
+        best.into_sorted_vec()
+    }
+
+    fn search(
+        &self,
+        lo: usize,
+        hi: usize,
+        depth: usize,
+        query: &Point<D>,
+        k: usize,
+        best: &mut BinaryHeap<(u128, usize)>,
+    ) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let index = self.order[mid];
+        let point = &self.points[index];
+        let candidate = (distance(query, point), index);
+        if best.len() < k {
+            best.push(candidate);
+        } else if best.peek().is_some_and(|worst| candidate < *worst) {
+            best.pop();
+            best.push(candidate);
+        }
+
+        let axis = depth % D;
+        let (near, far) = if query[axis] < point[axis] {
+            ((lo, mid), (mid + 1, hi))
+        } else {
+            ((mid + 1, hi), (lo, mid))
+        };
+        self.search(near.0, near.1, depth + 1, query, k, best);
+        // Points on the far side are at least as far as the splitting plane,
+        // and one at the same distance may still win on its index.
+        let plane = (query[axis].abs_diff(point[axis]) as u128).pow(2);
+        if best.len() < k || best.peek().is_some_and(|worst| plane <= worst.0) {
+            self.search(far.0, far.1, depth + 1, query, k, best);
+        }
+    }
+
+    /// Every pair of points `(distance, i, j)` with `i < j`, nearest first.
+    pub fn pairs(&self) -> Pairs<'_, D> {
+        let mut pairs = Pairs {
+            tree: self,
+            neighbours: vec![vec![]; self.len()],
+            next: vec![0; self.len()],
+            heap: BinaryHeap::new(),
+        };
+        for i in 0..self.len() {
+            pairs.fetch(i, INITIAL_NEIGHBOURS);
+            pairs.push_next(i);
+        }
+        pairs
+    }
+}
+
+/// Iterator over the pairs of a [`KdTree`], see [`KdTree::pairs`].
+#[derive(Clone, Debug)]
+pub struct Pairs<'t, const D: usize> {
+    tree: &'t KdTree<D>,
+    /// The nearest neighbours of each point fetched so far, nearest first.
+    neighbours: Vec<Vec<(u128, usize)>>,
+    /// How many of each point's neighbours have been put on the heap.
+    next: Vec<usize>,
+    heap: BinaryHeap<Reverse<(u128, usize, usize)>>,
+}
+
+impl<const D: usize> Pairs<'_, D> {
+    /// Fetches the `k` nearest neighbours of point `i`, other than itself.
+    /// Ties are ordered by index, so the earlier neighbours stay in place.
+    fn fetch(&mut self, i: usize, k: usize) {
+        let point = &self.tree.points[i];
+        self.neighbours[i] = self
+            .tree
+            .nearest(point, k + 1)
+            .into_iter()
+            .filter(|&(_, j)| j != i)
+            .take(k)
+            .collect();
+    }
+
+    /// Puts the next neighbour of point `i` on the heap, fetching twice as
+    /// many neighbours when they run out.
+    fn push_next(&mut self, i: usize) {
+        let fetched = self.neighbours[i].len();
+        if self.next[i] == fetched && fetched < self.tree.len() - 1 {
+            self.fetch(i, 2 * fetched.max(1));
+        }
+        if let Some(&(distance, j)) = self.neighbours[i].get(self.next[i]) {
+            self.heap.push(Reverse((distance, i, j)));
+            self.next[i] += 1;
+        }
+    }
+}
+
+impl<const D: usize> Iterator for Pairs<'_, D> {
+    type Item = (u128, usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Reverse((distance, i, j)) = self.heap.pop()?;
+            self.push_next(i);
+            // Each pair is in the neighbours of both points, only the smaller
+            // index reports it.
+            if i < j {
+                return Some((distance, i, j));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn random_points(rng: &mut Rng, n: usize, range: u64) -> Vec<Point<3>> {
+        (0..n)
+            .map(|_| [(); 3].map(|_| rng.below(range) as i64 - range as i64 / 2))
+            .collect()
+    }
+
+    #[test]
+    fn nearest_matches_scan() {
+        let mut rng = Rng::new(1);
+        for (n, range) in [(0, 10), (1, 10), (50, 8), (300, 1000)] {
+            let tree = KdTree::new(random_points(&mut rng, n, range));
+            for _ in 0..20 {
+                let query = random_points(&mut rng, 1, range)[0];
+                let k = rng.index(n + 2);
+                let mut expected: Vec<_> = (0..n)
+                    .map(|i| (distance(&query, &tree.points()[i]), i))
+                    .collect();
+                expected.sort_unstable();
+                expected.truncate(k);
+                assert_eq!(tree.nearest(&query, k), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn pairs_by_distance() {
+        let mut rng = Rng::new(2);
+        // Small ranges give many ties and duplicate points.
+        for (n, range) in [(0, 10), (1, 10), (2, 10), (40, 4), (120, 1000)] {
+            let tree = KdTree::new(random_points(&mut rng, n, range));
+            let points = tree.points();
+            let mut expected = vec![];
+            for j in 0..n {
+                for i in 0..j {
+                    expected.push((distance(&points[i], &points[j]), i, j));
+                }
+            }
+            expected.sort_unstable();
+            assert_eq!(tree.pairs().collect::<Vec<_>>(), expected);
+        }
+    }
+}
@@ -1,11 +1,43 @@
+mod answer;
+pub mod bigint;
+pub mod bitset;
+pub mod cli;
+pub mod dlx;
+pub mod gf2;
+pub mod ilp;
 mod input;
+pub mod ints;
+pub mod kdtree;
+pub mod mst;
+pub mod params;
+pub mod parse;
+pub mod rng;
+pub mod visualize;
 
+pub use answer::Answer;
 pub use input::{download_input_file, read_input_file};
 
 /// Runs and times each day for the year.
 /// Each day is timed and each of its steps, including preprocessing.
 ///
-/// Preprocessing can optionally be provided before each part.
+/// Preprocessing can optionally be provided before each part. It returns a
+/// `Result`, and if it fails the error is printed in place of the day's
+/// results, which for parse errors is an annotated snippet of the input.
+/// Parts return an [`Answer`], which is a `Result` for parts that can fail on
+/// well-formed input.
+///
+/// A day declared `with Params` passes its parameters to preprocessing as
+/// `&$day::Params`, see [`params!`]. They start from `Default` and are
+/// overridden by `--param [DAY.]NAME=VALUE` on the command line, see
+/// [`cli::Args::apply`].
+///
+/// A day with preprocessing can name a function after its parts, called as
+/// `visualize(&input, &mut recorder)` when `--visualize FORMAT` is given, that
+/// records the steps of the solution, see [`visualize`].
+///
+/// A leading `generate = path;` names a function taking a
+/// [`cli::Generate`] and returning the input or an error, which is called
+/// instead of running the days when the binary is invoked as `aoc gen`.
 ///
 /// Usage example:
 /// ```
@@ -13,19 +45,49 @@ pub use input::{download_input_file, read_input_file};
 /// mod dayYY;
 /// mod dayXY;
 /// mod dayYX;
+/// mod dayZZ;
 ///
 /// aoc!(
+///     generate = generate::generate;
 ///     day01 => part1;
 ///     dayYY => part1, part2;
 ///     dayXY => process => part1;
-///     dayYX => process => part1, part2;
+///     dayYX => process => part1, part2 => visualize;
+///     dayZZ with Params => process => part1, part2;
 /// );
 /// ```
 #[macro_export]
 macro_rules! aoc {
+    (generate = $generate:path; $($tree:tt)*) => {{
+        let args = $crate::cli::Args::from_env();
+        match &args.generate {
+            Some(request) => match $generate(request) {
+                Ok(input) => print!("{}", input),
+                Err(err) => {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            },
+            None => $crate::__aoc_run!(args; $($tree)*),
+        }
+    }};
+
     ($($tree:tt)*) => {{
+        let args = $crate::cli::Args::from_env();
+        if args.generate.is_some() {
+            eprintln!("no input generators for this year");
+            std::process::exit(1);
+        }
+        $crate::__aoc_run!(args; $($tree)*);
+    }};
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __aoc_run {
+    ($args:ident; $($tree:tt)*) => {{
         let start = std::time::Instant::now();
-        __aoc_impl!($($tree)*);
+        __aoc_impl!($args; $($tree)*);
         let elapsed = start.elapsed();
         println!("Total elapsed: {:.3?}", elapsed);
     }};
@@ -34,47 +96,188 @@ macro_rules! aoc {
 #[macro_export]
 #[doc(hidden)]
 macro_rules! run_day {
-    ( @run $year:literal $day:ident $( @process $process:ident )? @parts $($part:ident),+ $(,)?) => {{
+    ( @run $args:ident $year:literal $day:ident $( @process $process:ident [$($params:ident)?] )? @parts $($part:ident),+ $(,)? $( @visualize $visualize:ident )?) => {{
         let day_usize = stringify!($day).strip_prefix("day").unwrap_or(stringify!($day)).parse::<usize>().expect("Could not parse day ident");
-        download_input_file($year, day_usize).expect("Could not download input file");
-        let input = &read_input_file($year, day_usize).expect("Could not read input file");
-
-        let start_day = std::time::Instant::now();
-
-        println!("Day {}: ", stringify!($day));
-
-        $(
-            let start = std::time::Instant::now();
-            let input = $day::$process(input);
-            let elapsed = start.elapsed();
-            println!("  {:<15} {:<20}elapsed: {:.3?}", "preprocessing", "", elapsed);
-        )?
-
-        $(
-            let start = std::time::Instant::now();
-            let result = $day::$part(&input);
-            let elapsed = start.elapsed();
-            println!("  {:<15} {:<20}elapsed: {:.3?}", stringify!($part), result, elapsed);
-        )+
-
-        let elapsed = start_day.elapsed();
-        println!("elapsed day: {:.3?}", elapsed);
-        println!("");
+        if $args.runs(day_usize) {
+            let input = &match &$args.input {
+                Some(path) => std::fs::read_to_string(path).expect("Could not read input file"),
+                None => {
+                    download_input_file($year, day_usize).expect("Could not download input file");
+                    read_input_file($year, day_usize).expect("Could not read input file")
+                }
+            };
+
+            let start_day = std::time::Instant::now();
+
+            println!("Day {}: ", stringify!($day));
+
+            'day: {
+                $(
+                    let start = std::time::Instant::now();
+                    let input = match $crate::__aoc_process!($args day_usize $day $process input $($params)?) {
+                        Ok(input) => input,
+                        Err(err) => {
+                            println!("  {:<15} failed:\n{}", "preprocessing", err);
+                            break 'day;
+                        }
+                    };
+                    let elapsed = start.elapsed();
+                    println!("  {:<15} {:<20}elapsed: {:.3?}", "preprocessing", "", elapsed);
+                )?
+
+                $(
+                    let start = std::time::Instant::now();
+                    let result = $crate::Answer::to_answer(&$day::$part(&input));
+                    let elapsed = start.elapsed();
+                    println!("  {:<15} {:<19} elapsed: {:.3?}", stringify!($part), result, elapsed);
+                )+
+
+                $(
+                    if let Some(format) = $args.visualize {
+                        let dir = std::path::Path::new("visualize").join(stringify!($day));
+                        let mut recorder = $crate::visualize::Recorder::new(format, dir);
+                        match $day::$visualize(&input, &mut recorder).and_then(|()| recorder.finish()) {
+                            Ok(summary) => println!("  {:<15} {}", "visualize", summary),
+                            Err(err) => println!("  {:<15} failed: {}", "visualize", err),
+                        }
+                    }
+                )?
+            }
+
+            let elapsed = start_day.elapsed();
+            println!("elapsed day: {:.3?}", elapsed);
+            println!("");
+        }
+    }};
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __aoc_process {
+    ($args:ident $day_usize:ident $day:ident $process:ident $input:ident) => {
+        match $args.apply($day_usize, &mut ()) {
+            Ok(()) => $day::$process($input).map_err(|err| err.to_string()),
+            Err(err) => Err(err.to_string()),
+        }
+    };
+
+    ($args:ident $day_usize:ident $day:ident $process:ident $input:ident $params:ident) => {{
+        let mut params = $day::$params::default();
+        match $args.apply($day_usize, &mut params) {
+            Ok(()) => $day::$process($input, &params).map_err(|err| err.to_string()),
+            Err(err) => Err(err.to_string()),
+        }
     }};
 }
 
 #[macro_export]
 #[doc(hidden)]
 macro_rules! __aoc_impl {
-    ($year:literal $day:ident => $process:ident => $($part:ident),+ $(,)? ;  $($rest:tt)*) => {{
-        run_day!(@run $year $day @process $process @parts $($part, )+);
-        __aoc_impl!($($rest)*);
+    ($args:ident; $year:literal $day:ident with $params:ident => $process:ident => $($part:ident),+ $(,)? $(=> $visualize:ident)? ;  $($rest:tt)*) => {{
+        run_day!(@run $args $year $day @process $process [$params] @parts $($part, )+ $(@visualize $visualize)?);
+        __aoc_impl!($args; $($rest)*);
     }};
 
-    ($year:literal $day:ident => $($part:ident),+ $(,)? ;  $($rest:tt)*) => {{
-        run_day!(@run $year $day @parts $($part, )+);
-        __aoc_impl!($($rest)*);
+    ($args:ident; $year:literal $day:ident => $process:ident => $($part:ident),+ $(,)? $(=> $visualize:ident)? ;  $($rest:tt)*) => {{
+        run_day!(@run $args $year $day @process $process [] @parts $($part, )+ $(@visualize $visualize)?);
+        __aoc_impl!($args; $($rest)*);
     }};
 
-    () => {{}};
+    ($args:ident; $year:literal $day:ident => $($part:ident),+ $(,)? ;  $($rest:tt)*) => {{
+        run_day!(@run $args $year $day @parts $($part, )+);
+        __aoc_impl!($args; $($rest)*);
+    }};
+
+    ($args:ident; ) => {{}};
+}
+
+/// Generates tests that run an example through `process` and the parts, and
+/// compare the answers.
+///
+/// Expands to a test module, `example` unless given a `name`, which resolves
+/// `process`, `part1` and `part2` in the scope it is invoked from. Parameters
+/// override the fields of the day's `Params::default()` and are passed to
+/// `process`, so a day with parameters needs `params = {}` to test with the
+/// defaults.
+///
+/// Usage example:
+/// ```ignore
+/// #[cfg(test)]
+/// mod tests {
+///     use super::*;
+///
+///     aoc_test!(day08, example = SAMPLE_INPUT, part1 = 40, part2 = 25272, params = { limit: 10 });
+///     aoc_test!(day08, name = larger, example = LARGER_INPUT, part2 = 1);
+/// }
+/// ```
+#[macro_export]
+macro_rules! aoc_test {
+    ($day:ident, name = $name:ident, $($rest:tt)*) => {
+        $crate::__aoc_test!(@module $day $name $($rest)*);
+    };
+
+    ($day:ident, $($rest:tt)*) => {
+        $crate::__aoc_test!(@module $day example $($rest)*);
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __aoc_test {
+    (
+        @module $day:ident $name:ident example = $example:expr
+        $(, part1 = $part1:expr)?
+        $(, part2 = $part2:expr)?
+        $(, params = $params:tt)?
+        $(,)?
+    ) => {
+        #[allow(clippy::needless_update)]
+        mod $name {
+            #[allow(unused_imports)]
+            use super::*;
+
+            macro_rules! example_input {
+                () => {
+                    $crate::__aoc_test!(@process ($example) $($params)?)
+                };
+            }
+
+            #[test]
+            fn process() {
+                if let Err(err) = example_input!() {
+                    panic!("{} {} failed to process:\n{}", stringify!($day), stringify!($name), err);
+                }
+            }
+
+            $(
+                #[test]
+                fn part1() {
+                    let input = example_input!().unwrap();
+                    assert_eq!(super::part1(&input), $part1, "{} {} part1", stringify!($day), stringify!($name));
+                }
+            )?
+
+            $(
+                #[test]
+                fn part2() {
+                    let input = example_input!().unwrap();
+                    assert_eq!(super::part2(&input), $part2, "{} {} part2", stringify!($day), stringify!($name));
+                }
+            )?
+        }
+    };
+
+    (@process ($example:expr)) => {
+        super::process($example)
+    };
+
+    (@process ($example:expr) { $($key:ident : $value:expr),* $(,)? }) => {
+        super::process(
+            $example,
+            &super::Params {
+                $($key: $value,)*
+                ..Default::default()
+            },
+        )
+    };
 }
@@ -0,0 +1,218 @@
+//! Minimum spanning trees, or forests when the graph is not connected.
+//!
+//! [`kruskal`] reads edges lazily in increasing weight order and stops once
+//! everything is connected, which suits streams like
+//! [`KdTree::pairs`](crate::kdtree::KdTree::pairs). [`prim`] takes a distance
+//! function instead and runs in `O(n^2)`, for complete graphs too dense to
+//! list their edges.
+
+use std::ops::Add;
+
+/// Disjoint sets of `0..len` with union by size and path halving.
+#[derive(Clone, Debug)]
+pub struct DisjointSet {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+    count: usize,
+}
+
+impl DisjointSet {
+    pub fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            size: vec![1; len],
+            count: len,
+        }
+    }
+
+    /// The representative of the set containing `x`.
+    pub fn find(&mut self, mut x: usize) -> usize {
+        while self.parent[x] != x {
+            self.parent[x] = self.parent[self.parent[x]];
+            x = self.parent[x];
+        }
+        x
+    }
+
+    /// Joins the sets of `a` and `b`, returning the sizes of the two sets if
+    /// they were different.
+    pub fn union(&mut self, a: usize, b: usize) -> Option<(usize, usize)> {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return None;
+        }
+        let sizes = (self.size[a], self.size[b]);
+        let (small, large) = if sizes.0 < sizes.1 { (a, b) } else { (b, a) };
+        self.parent[small] = large;
+        self.size[large] += self.size[small];
+        self.count -= 1;
+        Some(sizes)
+    }
+
+    /// The number of sets.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The size of every set, in no particular order.
+    pub fn sizes(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.parent.len())
+            .filter(|&x| self.parent[x] == x)
+            .map(|x| self.size[x])
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Edge<W> {
+    pub a: usize,
+    pub b: usize,
+    pub weight: W,
+}
+
+#[derive(Clone, Debug)]
+pub struct SpanningForest<W> {
+    /// The edges in the order they were added.
+    pub edges: Vec<Edge<W>>,
+    /// The sizes of the two components joined by each edge.
+    pub merges: Vec<(usize, usize)>,
+    pub weight: W,
+    /// The components of the forest.
+    pub components: DisjointSet,
+}
+
+impl<W: Copy + Default + Add<Output = W>> SpanningForest<W> {
+    fn new(nodes: usize) -> Self {
+        Self {
+            edges: vec![],
+            merges: vec![],
+            weight: W::default(),
+            components: DisjointSet::new(nodes),
+        }
+    }
+
+    fn add(&mut self, edge: Edge<W>) -> bool {
+        let Some(sizes) = self.components.union(edge.a, edge.b) else {
+            return false;
+        };
+        self.edges.push(edge);
+        self.merges.push(sizes);
+        self.weight = self.weight + edge.weight;
+        true
+    }
+
+    /// Whether the forest is a single tree.
+    pub fn is_connected(&self) -> bool {
+        self.components.count() <= 1
+    }
+}
+
+/// Kruskal's algorithm over `edges` as `(a, b, weight)`, which must come in
+/// increasing weight order. Stops reading once all `nodes` are connected.
+pub fn kruskal<W, I>(nodes: usize, edges: I) -> SpanningForest<W>
+where
+    W: Copy + Default + Add<Output = W>,
+    I: IntoIterator<Item = (usize, usize, W)>,
+{
+    let mut forest = SpanningForest::new(nodes);
+    if forest.is_connected() {
+        return forest;
+    }
+    for (a, b, weight) in edges {
+        if forest.add(Edge { a, b, weight }) && forest.is_connected() {
+            break;
+        }
+    }
+    forest
+}
+
+/// Prim's algorithm on the complete graph of `nodes` with edge weights
+/// `distance(a, b)`, growing a single tree from node `0`.
+pub fn prim<W, F>(nodes: usize, distance: F) -> SpanningForest<W>
+where
+    W: Copy + Default + Ord + Add<Output = W>,
+    F: Fn(usize, usize) -> W,
+{
+    let mut forest = SpanningForest::new(nodes);
+    // The cheapest edge from the tree to every node outside it.
+    let mut cheapest: Vec<Option<Edge<W>>> = (0..nodes)
+        .map(|b| {
+            (b > 0).then(|| Edge {
+                a: 0,
+                b,
+                weight: distance(0, b),
+            })
+        })
+        .collect();
+    while let Some(next) = cheapest.iter().flatten().min_by_key(|e| e.weight).copied() {
+        forest.add(next);
+        cheapest[next.b] = None;
+        for (b, edge) in cheapest.iter_mut().enumerate() {
+            if let Some(edge) = edge {
+                let weight = distance(next.b, b);
+                if weight < edge.weight {
+                    *edge = Edge {
+                        a: next.b,
+                        b,
+                        weight,
+                    };
+                }
+            }
+        }
+    }
+    forest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    #[test]
+    fn disjoint_sets() {
+        let mut sets = DisjointSet::new(5);
+        assert_eq!(sets.union(0, 1), Some((1, 1)));
+        assert_eq!(sets.union(2, 1), Some((1, 2)));
+        assert_eq!(sets.union(0, 2), None);
+        assert_eq!(sets.count(), 3);
+        let mut sizes: Vec<_> = sets.sizes().collect();
+        sizes.sort_unstable();
+        assert_eq!(sizes, vec![1, 1, 3]);
+    }
+
+    #[test]
+    fn kruskal_and_prim_agree() {
+        let mut rng = Rng::new(3);
+        for nodes in [1, 2, 5, 40] {
+            let points: Vec<_> = (0..nodes).map(|_| rng.below(1000) as i64).collect();
+            let distance = |a: usize, b: usize| points[a].abs_diff(points[b]);
+            let mut edges: Vec<_> = (0..nodes)
+                .flat_map(|a| (0..a).map(move |b| (a, b)))
+                .map(|(a, b)| (a, b, distance(a, b)))
+                .collect();
+            edges.sort_by_key(|e| e.2);
+
+            let kruskal = kruskal(nodes, edges);
+            let prim = prim(nodes, distance);
+            for forest in [&kruskal, &prim] {
+                assert!(forest.is_connected());
+                assert_eq!(forest.edges.len(), nodes - 1);
+            }
+            assert_eq!(kruskal.weight, prim.weight);
+            // On a line the tree links neighbours, so its weight is the span.
+            let span = points.iter().max().unwrap() - points.iter().min().unwrap();
+            assert_eq!(kruskal.weight, span as u64);
+        }
+    }
+
+    #[test]
+    fn forests() {
+        let edges = [(0, 1, 1), (2, 3, 2), (1, 0, 3)];
+        let forest = kruskal(5, edges);
+        assert!(!forest.is_connected());
+        assert_eq!(forest.components.count(), 3);
+        assert_eq!(forest.weight, 3);
+        assert_eq!(forest.merges, vec![(1, 1), (1, 1)]);
+        let last = forest.edges.last().unwrap();
+        assert_eq!((last.a, last.b), (2, 3));
+    }
+}
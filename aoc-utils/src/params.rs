@@ -0,0 +1,184 @@
+//! Typed per-day puzzle parameters.
+//!
+//! Some puzzles change a constant between the example and the real input.
+//! A day declares those constants with [`params!`](crate::params!), gets the
+//! real values from `Default`, and examples or the command line override
+//! single fields by name.
+
+use std::fmt;
+
+/// Parameters that can be overridden by name from strings.
+pub trait Params: Default {
+    /// The names of the parameters.
+    const NAMES: &'static [&'static str];
+
+    /// Sets the parameter `name` from its string form.
+    fn set(&mut self, name: &str, value: &str) -> Result<(), ParamError>;
+}
+
+/// Days without parameters.
+impl Params for () {
+    const NAMES: &'static [&'static str] = &[];
+
+    fn set(&mut self, name: &str, _value: &str) -> Result<(), ParamError> {
+        Err(ParamError::Unknown {
+            name: name.to_owned(),
+            known: Self::NAMES,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParamError {
+    Unknown {
+        name: String,
+        known: &'static [&'static str],
+    },
+    Invalid {
+        name: String,
+        value: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown { name, known: [] } => {
+                write!(f, "unknown parameter `{}`, this day takes none", name)
+            }
+            Self::Unknown { name, known } => write!(
+                f,
+                "unknown parameter `{}`, expected one of: {}",
+                name,
+                known.join(", ")
+            ),
+            Self::Invalid {
+                name,
+                value,
+                reason,
+            } => write!(f, "invalid value `{}` for `{}`: {}", value, name, reason),
+        }
+    }
+}
+
+impl std::error::Error for ParamError {}
+
+/// Parses `value` for the parameter `name`.
+#[doc(hidden)]
+pub fn parse_value<T>(name: &str, value: &str) -> Result<T, ParamError>
+where
+    T: std::str::FromStr,
+    T::Err: fmt::Display,
+{
+    value.parse().map_err(|err: T::Err| ParamError::Invalid {
+        name: name.to_owned(),
+        value: value.to_owned(),
+        reason: err.to_string(),
+    })
+}
+
+/// Declares a parameter struct with the defaults used for the real input.
+///
+/// Usage example:
+/// ```
+/// aoc_utils::params! {
+///     pub struct Params {
+///         /// How many pairs to connect.
+///         pub limit: usize = 1000,
+///     }
+/// }
+///
+/// use aoc_utils::params::Params as _;
+///
+/// let mut params = Params::default();
+/// params.set("limit", "10").unwrap();
+/// assert_eq!(params.limit, 10);
+/// ```
+#[macro_export]
+macro_rules! params {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[$field_attr:meta])*
+                $field_vis:vis $field:ident : $ty:ty = $default:expr
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        #[derive(Clone, Debug, PartialEq)]
+        $vis struct $name {
+            $(
+                $(#[$field_attr])*
+                $field_vis $field: $ty,
+            )*
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self {
+                    $($field: $default,)*
+                }
+            }
+        }
+
+        impl $crate::params::Params for $name {
+            const NAMES: &'static [&'static str] = &[$(stringify!($field)),*];
+
+            fn set(&mut self, name: &str, value: &str) -> Result<(), $crate::params::ParamError> {
+                match name {
+                    $(stringify!($field) => self.$field = $crate::params::parse_value(name, value)?,)*
+                    _ => {
+                        return Err($crate::params::ParamError::Unknown {
+                            name: name.to_owned(),
+                            known: Self::NAMES,
+                        });
+                    }
+                }
+                Ok(())
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    crate::params! {
+        struct Example {
+            limit: usize = 1000,
+            name: String = "real".to_owned(),
+        }
+    }
+
+    #[test]
+    fn defaults_and_overrides() {
+        let mut params = Example::default();
+        assert_eq!(params.limit, 1000);
+        params.set("limit", "10").unwrap();
+        params.set("name", "example").unwrap();
+        assert_eq!(
+            params,
+            Example {
+                limit: 10,
+                name: "example".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let mut params = Example::default();
+        assert_eq!(
+            params.set("limt", "10").unwrap_err().to_string(),
+            "unknown parameter `limt`, expected one of: limit, name"
+        );
+        assert_eq!(
+            params.set("limit", "-1").unwrap_err().to_string(),
+            "invalid value `-1` for `limit`: invalid digit found in string"
+        );
+        assert!(().set("limit", "10").is_err());
+    }
+}
@@ -0,0 +1,354 @@
+//! Reusable `winnow` parsers for common puzzle input formats.
+//!
+//! List parsers require at least one item and never consume a trailing
+//! separator, so they compose with [`finish`] which skips any trailing
+//! whitespace before requiring the end of input. Once a comma or a non-blank
+//! line has started a new item, failing to parse it is an error rather than
+//! the end of the list, so failures are reported where they happen.
+//!
+//! [`parse_all`] turns failures into a [`ParseReport`], which renders the
+//! offending line with a caret under the failing character together with the
+//! `StrContext` labels and expectations collected on the way.
+
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use winnow::{
+    ModalResult, Parser,
+    ascii::{Int, Uint, dec_int, dec_uint, line_ending, multispace0, space0, space1},
+    combinator::{
+        alt, cut_err, eof, fail, not, peek, repeat_till, separated, separated_pair, terminated,
+    },
+    error::{ContextError, ErrMode, StrContext, StrContextValue},
+    stream::Stream,
+    token::none_of,
+};
+
+/// The error type of all parsers in this module.
+pub type Error = ErrMode<ContextError>;
+
+/// A `StrContext` describing what the parser expected.
+pub fn expected(desc: &'static str) -> StrContext {
+    StrContext::Expected(StrContextValue::Description(desc))
+}
+
+/// A `StrContext` naming what is being parsed.
+pub fn label(name: &'static str) -> StrContext {
+    StrContext::Label(name)
+}
+
+/// A parse failure located in its input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseReport {
+    /// 1-based line of the failure.
+    pub line: usize,
+    /// 1-based column of the failure, in characters.
+    pub column: usize,
+    /// The text of the failing line, without its line ending.
+    pub source_line: String,
+    /// What the parser was in the middle of, innermost first.
+    pub labels: Vec<String>,
+    /// What the parser expected to find.
+    pub expected: Vec<String>,
+}
+
+impl ParseReport {
+    /// Locates a failure at byte `offset` of `input`.
+    pub fn new(input: &str, offset: usize, error: &ContextError) -> Self {
+        let mut report = Self::at(input, offset);
+        for context in error.context() {
+            match context {
+                StrContext::Label(label) => report.labels.push(label.to_string()),
+                StrContext::Expected(value) => report.expected.push(value.to_string()),
+                _ => {}
+            }
+        }
+        report
+    }
+
+    /// A failure at byte `offset` of `input` that expected `what`, for
+    /// parsers not built on `winnow`.
+    pub fn expected(input: &str, offset: usize, what: impl Into<String>) -> Self {
+        let mut report = Self::at(input, offset);
+        report.expected.push(what.into());
+        report
+    }
+
+    fn at(input: &str, offset: usize) -> Self {
+        let offset = (0..=offset.min(input.len()))
+            .rfind(|&i| input.is_char_boundary(i))
+            .unwrap_or(0);
+        let line_start = input[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = input[offset..]
+            .find('\n')
+            .map_or(input.len(), |i| offset + i);
+
+        Self {
+            line: input[..offset].matches('\n').count() + 1,
+            column: input[line_start..offset].chars().count() + 1,
+            source_line: input[line_start..line_end]
+                .trim_end_matches('\r')
+                .to_owned(),
+            labels: vec![],
+            expected: vec![],
+        }
+    }
+}
+
+impl fmt::Display for ParseReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "parse error at line {}, column {}",
+            self.line, self.column
+        )?;
+        if let Some(label) = self.labels.first() {
+            write!(f, " while parsing {}", label)?;
+        }
+        writeln!(f)?;
+
+        let gutter = " ".repeat(self.line.to_string().len());
+        let at = if self.column > self.source_line.chars().count() {
+            "end of line"
+        } else {
+            "here"
+        };
+        let message = match self.expected.as_slice() {
+            [] => format!("unexpected input {}", at),
+            expected => format!("expected {}", expected.join(" or ")),
+        };
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", self.line, self.source_line)?;
+        write!(
+            f,
+            "{} | {}^ {}",
+            gutter,
+            " ".repeat(self.column - 1),
+            message
+        )
+    }
+}
+
+impl std::error::Error for ParseReport {}
+
+/// An unsigned decimal integer of any width. Fails instead of overflowing.
+pub fn unsigned<T: Uint>(input: &mut &str) -> ModalResult<T> {
+    dec_uint
+        .context(expected("unsigned integer"))
+        .parse_next(input)
+}
+
+/// A decimal integer with an optional sign. Fails instead of overflowing.
+pub fn signed<T: Int>(input: &mut &str) -> ModalResult<T> {
+    dec_int.context(expected("integer")).parse_next(input)
+}
+
+/// An inclusive range written as `a-b`.
+pub fn range<T: Uint>(input: &mut &str) -> ModalResult<RangeInclusive<T>> {
+    separated_pair(unsigned, '-', unsigned)
+        .map(|(start, end)| start..=end)
+        .parse_next(input)
+}
+
+/// One or more items separated by `sep`, where every separator must be
+/// followed by an item.
+fn separated_cut<'i, O, O2, P, S>(mut item: P, mut sep: S) -> impl Parser<&'i str, Vec<O>, Error>
+where
+    P: Parser<&'i str, O, Error>,
+    S: Parser<&'i str, O2, Error>,
+{
+    move |input: &mut &'i str| {
+        let mut items = vec![item.parse_next(input)?];
+        loop {
+            let checkpoint = input.checkpoint();
+            match sep.parse_next(input) {
+                Ok(_) => items.push(cut_err(item.by_ref()).parse_next(input)?),
+                Err(ErrMode::Backtrack(_)) => {
+                    input.reset(&checkpoint);
+                    return Ok(items);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+/// One or more items separated by commas.
+pub fn comma_separated<'i, O, P>(item: P) -> impl Parser<&'i str, Vec<O>, Error>
+where
+    P: Parser<&'i str, O, Error>,
+{
+    separated_cut(item, ',')
+}
+
+/// One or more items separated by spaces or tabs.
+pub fn space_separated<'i, O, P>(item: P) -> impl Parser<&'i str, Vec<O>, Error>
+where
+    P: Parser<&'i str, O, Error>,
+{
+    separated(1.., item, space1)
+}
+
+/// A line ending followed by a line that is neither blank nor the end of
+/// input.
+fn line_break<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    terminated(line_ending, not(alt((line_ending, eof)))).parse_next(input)
+}
+
+/// One or more items on consecutive lines.
+pub fn lines<'i, O, P>(item: P) -> impl Parser<&'i str, Vec<O>, Error>
+where
+    P: Parser<&'i str, O, Error>,
+{
+    separated_cut(item, line_break)
+}
+
+/// One or more blocks separated by a blank line.
+pub fn blocks<'i, O, P>(block: P) -> impl Parser<&'i str, Vec<O>, Error>
+where
+    P: Parser<&'i str, O, Error>,
+{
+    separated(1.., block, (line_ending, line_ending))
+}
+
+/// A grid of cells, one row per line. Every character up to the line ending
+/// must be a cell.
+pub fn grid<'i, O, P>(cell: P) -> impl Parser<&'i str, Vec<Vec<O>>, Error>
+where
+    P: Parser<&'i str, O, Error>,
+{
+    let row = repeat_till(1.., cell, peek(alt((line_ending, eof))));
+    lines(row.map(|(row, _): (Vec<O>, _)| row))
+}
+
+/// A [`grid`] whose rows all have as many cells as the first. A row of a
+/// different length is an error at its start.
+pub fn rect_grid<'i, O, P>(mut cell: P) -> impl Parser<&'i str, Vec<Vec<O>>, Error>
+where
+    P: Parser<&'i str, O, Error>,
+{
+    move |input: &mut &'i str| {
+        let mut width = None;
+        let row = |input: &mut &'i str| {
+            let start = input.checkpoint();
+            let (row, _): (Vec<O>, _) =
+                repeat_till(1.., cell.by_ref(), peek(alt((line_ending, eof)))).parse_next(input)?;
+            if *width.get_or_insert(row.len()) != row.len() {
+                input.reset(&start);
+                return fail
+                    .context(expected("as many cells as the first row"))
+                    .parse_next(input);
+            }
+            Ok(row)
+        };
+        lines(row).parse_next(input)
+    }
+}
+
+/// A grid of arbitrary characters, one row per line.
+pub fn char_grid(input: &mut &str) -> ModalResult<Vec<Vec<char>>> {
+    grid(none_of(['\n', '\r'])).parse_next(input)
+}
+
+/// A `key: value` pair.
+pub fn key_value<'i, K, V, PK, PV>(key: PK, value: PV) -> impl Parser<&'i str, (K, V), Error>
+where
+    PK: Parser<&'i str, K, Error>,
+    PV: Parser<&'i str, V, Error>,
+{
+    separated_pair(key, (':', space0), value)
+}
+
+/// Runs `parser` on the whole input, allowing trailing whitespace.
+pub fn finish<'i, O, P>(parser: P) -> impl Parser<&'i str, O, Error>
+where
+    P: Parser<&'i str, O, Error>,
+{
+    terminated(parser, multispace0)
+}
+
+/// Parses all of `input` with `parser`, allowing trailing whitespace.
+pub fn parse_all<'i, O, P>(parser: P, input: &'i str) -> std::result::Result<O, ParseReport>
+where
+    P: Parser<&'i str, O, Error>,
+{
+    finish(parser)
+        .parse(input)
+        .map_err(|err| ParseReport::new(input, err.offset(), err.inner()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winnow::{ascii::alpha1, combinator::alt};
+
+    #[test]
+    fn integers() {
+        assert_eq!(parse_all(unsigned::<u8>, "255\n"), Ok(255));
+        assert!(parse_all(unsigned::<u8>, "256").is_err());
+        assert_eq!(parse_all(signed::<i128>, "-12"), Ok(-12));
+        assert_eq!(
+            parse_all(unsigned::<u128>, "340282366920938463463374607431768211455"),
+            Ok(u128::MAX)
+        );
+    }
+
+    #[test]
+    fn ranges_and_lists() {
+        let actual = parse_all(comma_separated(range::<u64>), "11-22,95-115\n\n");
+        assert_eq!(actual, Ok(vec![11..=22, 95..=115]));
+
+        let actual = parse_all(lines(space_separated(signed::<i32>)), "1 -2\n3  4\n");
+        assert_eq!(actual, Ok(vec![vec![1, -2], vec![3, 4]]));
+    }
+
+    #[test]
+    fn blocks_of_lines() {
+        let input = "1\n2\n\n3\n";
+        let actual = parse_all(blocks(lines(unsigned::<u32>)), input);
+        assert_eq!(actual, Ok(vec![vec![1, 2], vec![3]]));
+    }
+
+    #[test]
+    fn grids() {
+        let actual = parse_all(char_grid, "#.\r\n.#\r\n");
+        assert_eq!(actual, Ok(vec![vec!['#', '.'], vec!['.', '#']]));
+
+        let actual = parse_all(grid(alt(('#'.value(1), '.'.value(0)))), "#.\n.#");
+        assert_eq!(actual, Ok(vec![vec![1, 0], vec![0, 1]]));
+
+        let report = parse_all(rect_grid(none_of(['\n'])), "ab\ncd\nefg\n").unwrap_err();
+        assert_eq!((report.line, report.column), (3, 1));
+        assert_eq!(report.expected, vec!["as many cells as the first row"]);
+    }
+
+    #[test]
+    fn key_values() {
+        let actual = parse_all(
+            lines(key_value(alpha1, space_separated(alpha1))),
+            "aaa: b c\nd: e",
+        );
+        assert_eq!(actual, Ok(vec![("aaa", vec!["b", "c"]), ("d", vec!["e"])]));
+    }
+
+    #[test]
+    fn rejects_trailing_garbage() {
+        let report = parse_all(lines(unsigned::<u32>), "1\n2\nx").unwrap_err();
+        assert_eq!((report.line, report.column), (3, 1));
+        assert_eq!(report.source_line, "x");
+    }
+
+    #[test]
+    fn report_rendering() {
+        let pair = separated_pair(unsigned::<u32>, ',', unsigned::<u32>).context(label("pair"));
+        let report = parse_all(lines(pair), "1,2\r\n3,x4\r\n").unwrap_err();
+        assert_eq!((report.line, report.column), (2, 3));
+        assert_eq!(
+            report.to_string(),
+            "parse error at line 2, column 3 while parsing pair
+  |
+2 | 3,x4
+  |   ^ expected unsigned integer"
+        );
+    }
+}
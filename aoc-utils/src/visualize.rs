@@ -0,0 +1,324 @@
+//! Step-by-step pictures of a day's state, for debugging.
+//!
+//! A day draws each step into a [`Frame`] and hands it to a [`Recorder`],
+//! which either prints it to the terminal with ANSI colours, writes one
+//! PPM or PNG image per frame, or collects the frames into an animated GIF.
+//! Small frames are scaled up in images so that single cells stay visible.
+
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Images are scaled by a whole factor to be at least this many pixels wide
+/// or high.
+const MIN_IMAGE_SIZE: usize = 512;
+
+/// Pause between frames printed to the terminal, so they can be followed.
+const ANSI_FRAME_DELAY: Duration = Duration::from_millis(40);
+
+/// Delay between GIF frames, in hundredths of a second.
+const GIF_FRAME_DELAY: u16 = 5;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const BLACK: Self = Self(0, 0, 0);
+    pub const WHITE: Self = Self(255, 255, 255);
+    pub const GREY: Self = Self(110, 110, 110);
+    pub const RED: Self = Self(220, 50, 50);
+    pub const GREEN: Self = Self(60, 190, 80);
+    pub const BLUE: Self = Self(60, 120, 230);
+    pub const YELLOW: Self = Self(230, 190, 40);
+}
+
+/// A picture of one step, `width` by `height` cells.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Frame {
+    pub fn new(width: usize, height: usize, background: Rgb) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![background; width * height],
+        }
+    }
+
+    /// A frame with one pixel per cell of `rows`, which must all be as long
+    /// as the first.
+    pub fn from_grid<T>(rows: &[Vec<T>], color: impl Fn(&T) -> Rgb) -> Self {
+        let width = rows.first().map_or(0, Vec::len);
+        let pixels: Vec<_> = rows.iter().flatten().map(color).collect();
+        assert_eq!(pixels.len(), width * rows.len(), "ragged grid");
+        Self {
+            width,
+            height: rows.len(),
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Rgb {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Colours the pixel at `(x, y)`, ignoring positions outside the frame.
+    pub fn set(&mut self, x: usize, y: usize, color: Rgb) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+
+    /// Draws a straight line including both ends.
+    pub fn line(&mut self, from: (usize, usize), to: (usize, usize), color: Rgb) {
+        let steps = from.0.abs_diff(to.0).max(from.1.abs_diff(to.1)).max(1);
+        let lerp = |a: usize, b: usize, i: usize| {
+            (a as f64 + (b as f64 - a as f64) * i as f64 / steps as f64).round() as usize
+        };
+        for i in 0..=steps {
+            self.set(lerp(from.0, to.0, i), lerp(from.1, to.1, i), color);
+        }
+    }
+
+    /// Draws the outline of the rectangle with opposite corners `a` and `b`.
+    pub fn rect(&mut self, a: (usize, usize), b: (usize, usize), color: Rgb) {
+        self.line(a, (b.0, a.1), color);
+        self.line((b.0, a.1), b, color);
+        self.line(b, (a.0, b.1), color);
+        self.line((a.0, b.1), a, color);
+    }
+
+    /// Each pixel becomes a `factor` by `factor` square.
+    pub fn scaled(&self, factor: usize) -> Self {
+        let mut scaled = Self::new(self.width * factor, self.height * factor, Rgb::BLACK);
+        for y in 0..scaled.height {
+            for x in 0..scaled.width {
+                scaled.pixels[y * scaled.width + x] = self.get(x / factor, y / factor);
+            }
+        }
+        scaled
+    }
+
+    fn rgb_bytes(&self) -> Vec<u8> {
+        self.pixels.iter().flat_map(|p| [p.0, p.1, p.2]).collect()
+    }
+
+    /// Binary PPM (`P6`) encoding.
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut ppm = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        ppm.extend(self.rgb_bytes());
+        ppm
+    }
+
+    /// Renders two rows per line of text with upper half blocks, the upper
+    /// row as foreground and the lower as background colour.
+    pub fn to_ansi(&self) -> String {
+        let mut out = String::new();
+        for y in (0..self.height).step_by(2) {
+            for x in 0..self.width {
+                let Rgb(r, g, b) = self.get(x, y);
+                write!(out, "\x1b[38;2;{};{};{}m", r, g, b).unwrap();
+                if y + 1 < self.height {
+                    let Rgb(r, g, b) = self.get(x, y + 1);
+                    write!(out, "\x1b[48;2;{};{};{}m", r, g, b).unwrap();
+                } else {
+                    out.push_str("\x1b[49m");
+                }
+                out.push('▀');
+            }
+            out.push_str("\x1b[0m\n");
+        }
+        out
+    }
+}
+
+/// Where a [`Recorder`] sends its frames.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Ansi,
+    Ppm,
+    Png,
+    Gif,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ansi" => Ok(Self::Ansi),
+            "ppm" => Ok(Self::Ppm),
+            "png" => Ok(Self::Png),
+            "gif" => Ok(Self::Gif),
+            _ => Err(format!(
+                "unknown format `{}`, expected ansi, ppm, png or gif",
+                s
+            )),
+        }
+    }
+}
+
+/// Writes frames in a [`Format`], image files go to `dir`.
+pub struct Recorder {
+    format: Format,
+    dir: PathBuf,
+    frames: usize,
+    gif: Option<gif::Encoder<BufWriter<File>>>,
+}
+
+impl Recorder {
+    pub fn new(format: Format, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            format,
+            dir: dir.into(),
+            frames: 0,
+            gif: None,
+        }
+    }
+
+    pub fn record(&mut self, frame: &Frame) -> io::Result<()> {
+        self.frames += 1;
+        if self.format == Format::Ansi {
+            let mut stdout = io::stdout().lock();
+            // Clear the screen once, then draw every frame over the last.
+            if self.frames == 1 {
+                write!(stdout, "\x1b[2J")?;
+            }
+            write!(stdout, "\x1b[H{}", frame.to_ansi())?;
+            stdout.flush()?;
+            std::thread::sleep(ANSI_FRAME_DELAY);
+            return Ok(());
+        }
+
+        let largest = frame.width.max(frame.height).max(1);
+        let frame = frame.scaled(MIN_IMAGE_SIZE.div_ceil(largest));
+        fs::create_dir_all(&self.dir)?;
+        let path = |ext| self.dir.join(format!("{:04}.{}", self.frames, ext));
+        match self.format {
+            Format::Ansi => unreachable!(),
+            Format::Ppm => fs::write(path("ppm"), frame.to_ppm()),
+            Format::Png => {
+                let file = BufWriter::new(File::create(path("png"))?);
+                let mut encoder = png::Encoder::new(file, frame.width as u32, frame.height as u32);
+                encoder.set_color(png::ColorType::Rgb);
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder
+                    .write_header()
+                    .and_then(|mut writer| writer.write_image_data(&frame.rgb_bytes()))
+                    .map_err(io::Error::other)
+            }
+            Format::Gif => {
+                let (width, height) = gif_size(&frame)?;
+                if self.gif.is_none() {
+                    let file = BufWriter::new(File::create(self.dir.join("animation.gif"))?);
+                    let mut encoder =
+                        gif::Encoder::new(file, width, height, &[]).map_err(io::Error::other)?;
+                    encoder
+                        .set_repeat(gif::Repeat::Infinite)
+                        .map_err(io::Error::other)?;
+                    self.gif = Some(encoder);
+                }
+                let mut gif_frame =
+                    gif::Frame::from_rgb_speed(width, height, &frame.rgb_bytes(), 10);
+                gif_frame.delay = GIF_FRAME_DELAY;
+                let encoder = self.gif.as_mut().expect("created above");
+                encoder.write_frame(&gif_frame).map_err(io::Error::other)
+            }
+        }
+    }
+
+    /// Finishes the output, describing what was written.
+    pub fn finish(self) -> io::Result<String> {
+        if let Some(encoder) = self.gif {
+            encoder.into_inner().map_err(io::Error::other)?.flush()?;
+        }
+        Ok(match self.format {
+            Format::Ansi => format!("{} frames", self.frames),
+            _ => format!("{} frames in {}", self.frames, self.dir.display()),
+        })
+    }
+}
+
+fn gif_size(frame: &Frame) -> io::Result<(u16, u16)> {
+    match (u16::try_from(frame.width), u16::try_from(frame.height)) {
+        (Ok(width), Ok(height)) => Ok((width, height)),
+        _ => Err(io::Error::other("frame too large for a GIF")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkerboard() -> Frame {
+        Frame::from_grid(&[vec![true, false], vec![false, true]], |&on| {
+            if on { Rgb::WHITE } else { Rgb::BLACK }
+        })
+    }
+
+    #[test]
+    fn drawing_and_encoding() {
+        let frame = checkerboard();
+        assert_eq!(
+            frame.to_ppm(),
+            b"P6\n2 2\n255\n\xff\xff\xff\0\0\0\0\0\0\xff\xff\xff".to_vec()
+        );
+        assert_eq!(
+            frame.to_ansi(),
+            "\x1b[38;2;255;255;255m\x1b[48;2;0;0;0m▀\x1b[38;2;0;0;0m\x1b[48;2;255;255;255m▀\x1b[0m\n"
+        );
+
+        let mut frame = Frame::new(4, 3, Rgb::BLACK);
+        frame.rect((0, 0), (3, 2), Rgb::RED);
+        let outline: Vec<_> = (0..3)
+            .map(|y| {
+                (0..4)
+                    .map(|x| frame.get(x, y) == Rgb::RED)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(
+            outline,
+            vec![
+                vec![true, true, true, true],
+                vec![true, false, false, true],
+                vec![true, true, true, true],
+            ]
+        );
+        assert_eq!(frame.scaled(2).get(7, 5), Rgb::RED);
+    }
+
+    #[test]
+    fn image_files() {
+        let dir = std::env::temp_dir().join(format!("aoc-visualize-{}", std::process::id()));
+        for (format, file, magic) in [
+            (Format::Ppm, "0002.ppm", &b"P6"[..]),
+            (Format::Png, "0002.png", &b"\x89PNG"[..]),
+            (Format::Gif, "animation.gif", &b"GIF89a"[..]),
+        ] {
+            let mut recorder = Recorder::new(format, &dir);
+            recorder.record(&checkerboard()).unwrap();
+            recorder.record(&checkerboard()).unwrap();
+            assert!(recorder.finish().unwrap().starts_with("2 frames in"));
+            assert!(fs::read(dir.join(file)).unwrap().starts_with(magic));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!("gif".parse(), Ok(Format::Gif));
+        assert!("jpeg".parse::<Format>().is_err());
+    }
+}